@@ -0,0 +1,45 @@
+//! Command line helpers shared by the `pathvis-rs` and `pathvis-solve`
+//! binaries, so both spell their options and errors the same way.
+
+use std::process;
+
+use planning::astar::Heuristic;
+use planning::world::Neighbors;
+
+/// Parse a heuristic name, where `none` turns the heuristic off
+pub fn parse_heuristic(flag: &str, value: &str) -> Result<Option<Heuristic>, String> {
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(None)
+    }
+    value.parse().map(Some).map_err(|_| format!(
+        "{} expects manhattan, euclidean, octile, chebyshev or none, not '{}'", flag, value))
+}
+
+/// Parse 4 or 8 neighbors, also accepting `cardinal` and `diagonal`
+pub fn parse_neighbors(flag: &str, value: &str) -> Result<Neighbors, String> {
+    value.parse().map_err(|_| format!("{} expects 4 or 8, not '{}'", flag, value))
+}
+
+/// Report a usage error for `program` and exit with status 2
+pub fn exit_with(program: &str, message: &str) -> ! {
+    eprintln!("{}: {}\nRun with --help to see the options", program, message);
+    process::exit(2);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parses_shared_options() {
+        assert!(parse_heuristic("--heuristic", "None").unwrap().is_none());
+        assert!(matches!(parse_heuristic("--heuristic", "octile"), Ok(Some(Heuristic::Octile))));
+        assert_eq!(parse_heuristic("--heuristic", "zigzag").unwrap_err(),
+                   "--heuristic expects manhattan, euclidean, octile, chebyshev or none, not 'zigzag'");
+        assert!(matches!(parse_neighbors("--neighbors", "diagonal"),
+                         Ok(Neighbors::CardinalAndDiagonal)));
+        assert_eq!(parse_neighbors("--neighbors", "6").unwrap_err(),
+                   "--neighbors expects 4 or 8, not '6'");
+    }
+}
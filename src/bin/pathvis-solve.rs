@@ -0,0 +1,162 @@
+//! Solve a map file with A* and print the result, no window needed.
+//!
+//! Exits with 1 when there is no path and 2 when the arguments or map are
+//! unusable.
+
+extern crate pathvis_rs;
+
+use std::env;
+use std::fs;
+use std::process;
+
+use pathvis_rs::*;
+use pathvis_rs::args;
+use pathvis_rs::planning::map::{self, Map};
+
+const USAGE: &str = "\
+Usage: pathvis-solve MAP [options]
+
+Options:
+  --start X,Y         start cell, overrides the S in the map
+  --goal X,Y          goal cell, overrides the G in the map
+  --heuristic NAME    manhattan, euclidean, octile, chebyshev or none (default euclidean)
+  --neighbors N       4 for cardinal moves or 8 to add diagonals (default 8)
+  -h, --help          show this message";
+
+struct Options {
+    map: String,
+    start: Option<(usize, usize)>,
+    goal: Option<(usize, usize)>,
+    heuristic: Option<Heuristic>,
+    neighbors: Neighbors,
+}
+
+fn parse_coords(flag: &str, value: &str) -> Result<(usize, usize), String> {
+    let mut parts = value.split(',').map(|part| part.trim().parse::<usize>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Ok((x, y)),
+        _ => Err(format!("{} expects X,Y not '{}'", flag, value)),
+    }
+}
+
+/// Parse the arguments that follow the program name, None if help was asked for
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Options>, String> {
+    let mut map = None;
+    let mut options = Options {
+        map: String::new(),
+        start: None,
+        goal: None,
+        heuristic: Some(Heuristic::Euclidean),
+        neighbors: Neighbors::CardinalAndDiagonal,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None)
+        }
+        if !arg.starts_with("--") {
+            if map.is_some() {
+                return Err(format!("unexpected argument '{}'", arg));
+            }
+            map = Some(arg);
+            continue
+        }
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--start"     => options.start = Some(parse_coords(&arg, &value)?),
+            "--goal"      => options.goal = Some(parse_coords(&arg, &value)?),
+            "--heuristic" => options.heuristic = args::parse_heuristic(&arg, &value)?,
+            "--neighbors" => options.neighbors = args::parse_neighbors(&arg, &value)?,
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+    options.map = map.ok_or_else(|| "no map file given".to_string())?;
+    Ok(Some(options))
+}
+
+fn exit_with(message: &str) -> ! {
+    args::exit_with("pathvis-solve", message)
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return
+        }
+        Err(e) => exit_with(&e),
+    };
+
+    let text = fs::read_to_string(&options.map)
+        .unwrap_or_else(|e| exit_with(&format!("could not read {}: {}", options.map, e)));
+    let map: Map = text.parse()
+        .unwrap_or_else(|e| exit_with(&format!("could not load {}: {}", options.map, e)));
+    let world = map.world;
+    let id_at = |coords: Option<(usize, usize)>, marked: Option<Id>, name: &str| match coords {
+        Some((x, y)) => world.id_at(x, y)
+            .map(Some)
+            .unwrap_or_else(|| exit_with(&format!("{} ({},{}) is outside the map", name, x, y))),
+        None => marked,
+    };
+    let start = id_at(options.start, map.start, "start");
+    let goal = id_at(options.goal, map.goal, "goal");
+
+    let mut cfg = AStarCfg::new()
+        .with_hueristic(options.heuristic)
+        .with_neighbors(options.neighbors);
+    cfg.start = start;
+    cfg.goal = goal;
+    let mut astar = AStar::from_cfg(cfg, world.clone())
+        .unwrap_or_else(|e| exit_with(&e.to_string()));
+
+    let mut expanded = 0;
+    let result = loop {
+        match astar.step() {
+            StepResult::Expanded { .. } => expanded += 1,
+            result => break result,
+        }
+    };
+
+    let path = astar.path().unwrap_or_default();
+    match result {
+        StepResult::Found { cost, .. } => {
+            let cells: Vec<String> = path.iter()
+                .map(|&id| format!("{:?}", world.coords_for(id).unwrap()))
+                .collect();
+            println!("Path: {}", cells.join(" "));
+            println!("Cost: {:.2}", cost);
+        }
+        _ => println!("No path"),
+    }
+    println!("Expanded: {}", expanded);
+    print!("{}", map::render(astar.world_view(), start, goal, &path));
+
+    if path.is_empty() {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_args() {
+        let options = parse(&["maps/detour.txt", "--start", "1, 2", "--heuristic", "none"])
+            .unwrap().unwrap();
+        assert_eq!(options.map, "maps/detour.txt");
+        assert_eq!(options.start, Some((1, 2)));
+        assert_eq!(options.goal, None);
+        assert!(options.heuristic.is_none());
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["--start", "1,2"]).is_err());
+        assert!(parse(&["a.txt", "b.txt"]).is_err());
+        assert!(parse(&["a.txt", "--goal", "1,2,3"]).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Command line options for the visualizer.
+
+use pathvis_rs::args;
+use planning::world::*;
+use planning::astar::*;
+use world_controller::MAX_SIDE;
+
+/// Largest cell that can be asked for on the command line
+pub const MAX_CELL_PIXELS: u32 = 256;
+
+/// Longest window side, well past any screen but safe for the graphics driver
+const MAX_WINDOW_SIDE: u32 = 16384;
+
+pub const USAGE: &str = "\
+Usage: pathvis-rs [options]
+
+Options:
+  --width N           cells across, at most 100 (default 10)
+  --height N          cells down, at most 100 (default 10)
+  --cell-pixels N     size of a cell on screen, at most 256 (default 64)
+  --font PATH         font for all text (default assets/FiraSans-Regular.ttf)
+  --heuristic NAME    manhattan, euclidean, octile, chebyshev or none (default none)
+  --neighbors N       4 for cardinal moves or 8 to add diagonals (default 4)
+  --map FILE          load a text map, its size replaces --width and --height
+  -h, --help          show this message";
+
+/// Settings for a launch of the visualizer
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub width: usize,
+    pub height: usize,
+    pub cell_pixels: u32,
+    pub font: String,
+    pub heuristic: Option<Heuristic>,
+    pub neighbors: Neighbors,
+    pub map: Option<String>,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            width: 10,
+            height: 10,
+            cell_pixels: 64,
+            font: "assets/FiraSans-Regular.ttf".to_string(),
+            heuristic: None,
+            neighbors: Neighbors::Cardinal,
+            map: None,
+            help: false,
+        }
+    }
+}
+
+impl Options {
+
+    /// Parse the arguments that follow the program name
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            if flag == "-h" || flag == "--help" {
+                options.help = true;
+                continue
+            }
+            let value = match args.next() {
+                Some(value) => value,
+                None => return Err(format!("{} needs a value", flag)),
+            };
+            match flag.as_str() {
+                "--width"       => options.width = parse_side(&flag, &value)?,
+                "--height"      => options.height = parse_side(&flag, &value)?,
+                "--cell-pixels" => options.cell_pixels = parse_cell_pixels(&flag, &value)?,
+                "--font"        => options.font = value,
+                "--heuristic"   => options.heuristic = args::parse_heuristic(&flag, &value)?,
+                "--neighbors"   => options.neighbors = args::parse_neighbors(&flag, &value)?,
+                "--map"         => options.map = Some(value),
+                _ => return Err(format!("unknown option '{}'", flag)),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Window size for a board with `board_cells` on its longest side, an
+    /// error if the cells make it too large to open
+    pub fn window_size(&self, board_cells: u32) -> Result<[u32; 2], String> {
+        let size = [self.cell_pixels * (board_cells + 5), self.cell_pixels * (board_cells + 2)];
+        if size[0] > MAX_WINDOW_SIDE {
+            return Err(format!("--cell-pixels {} makes a {} pixel wide window for {} cells, \
+                                use {} or fewer", self.cell_pixels, size[0], board_cells,
+                               MAX_WINDOW_SIDE / (board_cells + 5)))
+        }
+        Ok(size)
+    }
+}
+
+fn parse_side(flag: &str, value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(side) if side > 0 && side <= MAX_SIDE => Ok(side),
+        _ => Err(format!("{} expects a number of cells from 1 to {}, not '{}'", flag, MAX_SIDE, value)),
+    }
+}
+
+fn parse_cell_pixels(flag: &str, value: &str) -> Result<u32, String> {
+    match value.parse() {
+        Ok(pixels) if pixels > 0 && pixels <= MAX_CELL_PIXELS => Ok(pixels),
+        _ => Err(format!("{} expects a number of pixels from 1 to {}, not '{}'",
+                         flag, MAX_CELL_PIXELS, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse(&["--width", "20", "--height", "8", "--heuristic", "Octile",
+                              "--neighbors", "8", "--map", "maps/maze.txt"]).unwrap();
+        assert_eq!((options.width, options.height), (20, 8));
+        assert_eq!(options.cell_pixels, 64);
+        assert!(matches!(options.heuristic, Some(Heuristic::Octile)));
+        assert!(matches!(options.neighbors, Neighbors::CardinalAndDiagonal));
+        assert_eq!(options.map, Some("maps/maze.txt".to_string()));
+        assert!(parse(&["--heuristic", "none"]).unwrap().heuristic.is_none());
+        assert!(parse(&["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        assert!(parse(&["--width"]).is_err());
+        assert!(parse(&["--width", "0"]).is_err());
+        assert!(parse(&["--height", "101"]).is_err());
+        assert!(parse(&["--height", "100"]).is_ok());
+        assert!(parse(&["--heuristic", "zigzag"]).is_err());
+        assert!(parse(&["--neighbors", "6"]).is_err());
+        assert!(parse(&["--fullscreen", "yes"]).is_err());
+        assert!(parse(&["--cell-pixels", "0"]).is_err());
+        assert!(parse(&["--cell-pixels", "257"]).is_err());
+        assert!(parse(&["--cell-pixels", "-3"]).is_err());
+    }
+
+    #[test]
+    fn limits_window_size() {
+        let options = parse(&["--cell-pixels", "256"]).unwrap();
+        assert_eq!(options.window_size(10), Ok([3840, 3072]));
+        assert!(options.window_size(100).is_err());
+        assert!(Options::default().window_size(100).is_ok());
+    }
+}
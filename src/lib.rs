@@ -0,0 +1,14 @@
+//! Grid path planning, usable without the visualizer.
+//!
+//! `World`, `Cell`, `AStar` and `AStarCfg` are re-exported here as the stable
+//! entry points. The other planners and helpers live under `planning`, and
+//! `args` holds the option parsing both binaries share. The Piston GUI is
+//! built only with the `gui` feature, which is on by default.
+
+pub mod args;
+pub mod planning;
+
+pub use planning::world::{World, Cell, Id, Neighbors, Terrain};
+pub use planning::astar::{AStar, AStarCfg, Heuristic};
+pub use planning::planner::{Planner, StepResult};
+pub use planning::error::PlanningError;
@@ -2,6 +2,7 @@ extern crate piston;
 extern crate glutin_window;
 extern crate graphics;
 extern crate opengl_graphics;
+extern crate pathvis_rs;
 
 use opengl_graphics::{
     Filter,
@@ -18,22 +19,61 @@ use piston::event_loop::{
 };
 use piston::input::RenderEvent;
 use glutin_window::GlutinWindow;
+use std::env;
+use std::fs;
 
+use pathvis_rs::{args, planning};
 pub use planning::world::*;
 pub use planning::astar::*;
+pub use planning::planner::*;
 pub use world_controller::{WorldController, AppState};
 pub use world_view::{WorldView, WorldViewSettings};
+use cli::Options;
+use planning::map::Map;
 
-mod planning;
+mod cli;
 mod world_controller;
 mod world_view;
 
+/// Print a problem with the command line and quit
+fn exit_with(message: &str) -> ! {
+    args::exit_with("pathvis-rs", message)
+}
+
 fn main() {
+    let options = Options::parse(env::args().skip(1)).unwrap_or_else(|e| exit_with(&e));
+    if options.help {
+        println!("{}", cli::USAGE);
+        return
+    }
+
+    // a map brings its own size, start and goal
+    let cfg = AStarCfg::new()
+        .with_hueristic(options.heuristic.clone())
+        .with_neighbors(options.neighbors);
+    let mut world_controller = match &options.map {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .unwrap_or_else(|e| exit_with(&format!("could not read {}: {}", path, e)));
+            let map: Map = text.parse()
+                .unwrap_or_else(|e| exit_with(&format!("could not load {}: {}", path, e)));
+            WorldController::with_world(map.world, AStarCfg { start: map.start, goal: map.goal, ..cfg })
+        }
+        None => {
+            let world = World::new(options.width, options.height,
+                                   vec![Cell::Open; options.width * options.height]).unwrap();
+            WorldController::with_world(world, cfg)
+        }
+    };
+
     let opengl = OpenGL::V3_2;
-    let world_side_len: u32 = 10; // number of cells in x and y directions
-    let pts_per_cell: u32 = 64; // set the size of the cells on screen
-    let settings = WindowSettings::new("Path Visualizer", [pts_per_cell * (world_side_len + 5), 
-                                                           pts_per_cell * (world_side_len + 2)])
+    let world_width = world_controller.world().width() as u32; // number of cells in the x direction
+    let world_height = world_controller.world().height() as u32; // number of cells in the y direction
+    let pts_per_cell: u32 = options.cell_pixels; // set the size of the cells on screen
+    // the board gets a square area sized for the longest side
+    let board_cells = world_width.max(world_height);
+    let window_size = options.window_size(board_cells).unwrap_or_else(|e| exit_with(&e));
+    let settings = WindowSettings::new("Path Visualizer", window_size)
         .opengl(opengl)
         .srgb(false)
         .exit_on_esc(true);
@@ -42,20 +82,21 @@ fn main() {
     let mut events = Events::new(EventSettings::new().lazy(true));
     let mut gl = GlGraphics::new(opengl);
 
-    let mut world_controller = WorldController::new(world_side_len as usize);
     let mut world_view_settings = WorldViewSettings::new();
-    world_view_settings.size = (pts_per_cell * world_side_len) as f64;
+    world_view_settings.size = (pts_per_cell * board_cells) as f64;
     world_view_settings.font_size = (pts_per_cell as f64 / 4.0) as u32; // imperically determined ratio
     world_view_settings.position = [(pts_per_cell/2) as f64; 2]; 
     let world_view = WorldView::new(world_view_settings);
     let texture_settings = TextureSettings::new().filter(Filter::Nearest);
-    let ref mut glyphs = GlyphCache::new("assets/FiraSans-Regular.ttf", (), texture_settings)
-      .expect("Could not load font");
+    let glyphs = &mut GlyphCache::new(&options.font, (), texture_settings)
+      .unwrap_or_else(|e| exit_with(&format!("could not load font {}: {}", options.font, e)));
 
     while let Some(e) = events.next(&mut window) {
         world_controller.event(world_view.settings.position,
                                world_view.settings.size,
                                &e);
+        // only wake up for updates while playing back or running a search
+        events.set_lazy(!world_controller.playing && !world_controller.running);
         if let Some(args) = e.render_args() {
             gl.draw(args.viewport(), |c, g| {
                 use graphics::{clear};
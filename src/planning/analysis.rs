@@ -0,0 +1,160 @@
+//! Checks whether a heuristic can be trusted on a given world.
+//!
+//! A full Dijkstra pass out from the cell the heuristic aims at finds the true
+//! cost to it from every other cell. A heuristic is admissible if it never
+//! estimates more than that cost, which is what A* needs to return optimal
+//! paths. It is consistent if no single move lowers the estimate by more than
+//! the move costs, which also means cells never need to be expanded twice.
+
+use std::f32;
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::frontier::IndexedHeap;
+
+/// Slack allowed for floating point error
+const TOLERANCE: f32 = 1e-4;
+
+#[derive(Clone, Debug)]
+pub struct HeuristicReport {
+    /// True cost from each cell to the target, infinite if unreachable
+    pub cost_to_go: Vec<f32>,
+    /// Cost of the best path between start and goal, infinite if there is none
+    pub optimal_cost: f32,
+    /// Cells whose estimate is more than their true cost
+    pub overestimates: Vec<Id>,
+    /// Cells whose estimate drops by more than the cost of a move to a neighbor
+    pub triangle_breaks: Vec<Id>,
+}
+
+impl HeuristicReport {
+
+    pub fn admissible(&self) -> bool {
+        self.overestimates.is_empty()
+    }
+
+    pub fn consistent(&self) -> bool {
+        self.triangle_breaks.is_empty()
+    }
+}
+
+/// Cost of every reachable cell to `target`, moving as the config allows
+pub fn costs_to(cfg: &AStarCfg, world: &World, target: Id) -> Vec<f32> {
+    let mut cost = vec![f32::INFINITY; world.width() * world.height()];
+    let mut frontier = IndexedHeap::new(cost.len());
+    cost[target] = 0.0;
+    frontier.push(target, 0.0);
+    while let Some((id, g)) = frontier.pop() {
+        let coord = world.coords_for(id).unwrap();
+        for (x,y) in world.iter_neighbor_ids(id, cfg.neighbors).unwrap() {
+            let n = match world.id_at(x,y) { Some(n) => n, None => continue };
+            if let Some(Cell::Obstacle) = world.cell(n) { continue }
+            let new_cost = g + cfg.edge_cost(world, coord, (x,y));
+            if new_cost < cost[n] {
+                cost[n] = new_cost;
+                frontier.push(n, new_cost);
+            }
+        }
+    }
+    cost
+}
+
+/// Compare the config's heuristic against the true costs to the cell it aims
+/// at, the start unless the config searches forward
+pub fn check_heuristic(cfg: &AStarCfg, world: &World) -> Result<HeuristicReport, PlanningError> {
+
+    cfg.valid_for(world)?;
+
+    let target = cfg.target().unwrap();
+    let target_coord = world.coords_for(target).unwrap();
+    let cost_to_go = costs_to(cfg, world, target);
+    let mut overestimates = Vec::new();
+    let mut triangle_breaks = Vec::new();
+
+    for (id, &cost) in cost_to_go.iter().enumerate() {
+        if let Some(Cell::Obstacle) = world.cell(id) { continue }
+        let coord = world.coords_for(id).unwrap();
+        let h = cfg.estimate(coord, target_coord);
+        if h > cost + TOLERANCE {
+            overestimates.push(id);
+        }
+        let breaks = world.iter_neighbor_ids(id, cfg.neighbors).unwrap()
+            .filter(|&(x,y)| !matches!(world.cell_at(x,y), Some(Cell::Obstacle) | None))
+            .any(|n| h > cfg.edge_cost(world, coord, n) + cfg.estimate(n, target_coord) + TOLERANCE);
+        if breaks {
+            triangle_breaks.push(id);
+        }
+    }
+
+    // the search grows from the root, so its cost to the target is the answer
+    let optimal_cost = cost_to_go[cfg.root().unwrap()];
+    Ok(HeuristicReport { cost_to_go, optimal_cost, overestimates, triangle_breaks })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn mkcfg(w: &World, neighbors: Neighbors, h: Heuristic) -> AStarCfg {
+        AStarCfg::new()
+            .with_goal(w.id_at(0,0).unwrap())
+            .with_start(w.id_at(5,5).unwrap())
+            .with_hueristic(Some(h))
+            .with_neighbors(neighbors)
+    }
+
+    #[test]
+    fn finds_overestimates() {
+        let mut cells = vec![Cell::Open; 36];
+        cells[3 * 6 + 3] = Cell::Obstacle;
+        let w = World::new(6, 6, cells).unwrap();
+
+        let report = check_heuristic(&mkcfg(&w, Neighbors::Cardinal, Heuristic::Manhattan), &w).unwrap();
+        assert!(report.admissible() && report.consistent());
+        assert_eq!(report.cost_to_go[0], 10.0);
+        assert_eq!(report.cost_to_go[3 * 6 + 3], f32::INFINITY);
+
+        let diagonal = Neighbors::CardinalAndDiagonal;
+        for h in [Heuristic::Octile, Heuristic::Euclidean, Heuristic::Chebyshev].iter().cloned() {
+            let report = check_heuristic(&mkcfg(&w, diagonal, h), &w).unwrap();
+            assert!(report.admissible() && report.consistent());
+        }
+
+        // manhattan counts a diagonal move as two
+        let report = check_heuristic(&mkcfg(&w, diagonal, Heuristic::Manhattan), &w).unwrap();
+        assert!(report.overestimates.contains(&0));
+        assert!(report.triangle_breaks.contains(&0));
+        // cells in line with the start are still estimated exactly
+        assert!(!report.overestimates.contains(&w.id_at(5,0).unwrap()));
+    }
+
+    #[test]
+    fn optimal_cost_either_direction() {
+        let mut cells = vec![Cell::Open; 36];
+        cells[3 * 6 + 3] = Cell::Obstacle;
+        let w = World::new(6, 6, cells).unwrap();
+        let cfg = mkcfg(&w, Neighbors::Cardinal, Heuristic::Manhattan);
+        for &d in [Direction::Backward, Direction::Forward].iter() {
+            let report = check_heuristic(&cfg.clone().with_direction(d), &w).unwrap();
+            assert_eq!(report.optimal_cost, 10.0);
+        }
+    }
+
+    #[test]
+    fn inconsistent_but_admissible() {
+        let w = World::new(6, 6, vec![Cell::Open; 36]).unwrap();
+        // a single cell that underestimates, making its neighbors drop too fast
+        let dip = w.id_at(2,2).unwrap();
+        let cfg = mkcfg(&w, Neighbors::Cardinal, Heuristic::Manhattan)
+            .with_custom_heuristic(|(x, y), (sx, sy)| {
+                if (x, y) == (2, 2) { 0.0 } else { ((sx - x) + (sy - y)) as f32 }
+            });
+        let report = check_heuristic(&cfg, &w).unwrap();
+        assert!(report.admissible());
+        assert!(!report.consistent());
+        assert!(!report.triangle_breaks.contains(&dip));
+        assert!(report.triangle_breaks.contains(&w.id_at(1,2).unwrap()));
+    }
+}
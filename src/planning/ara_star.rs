@@ -0,0 +1,328 @@
+//! Anytime Repairing A*, a weighted A* that keeps improving its answer.
+//!
+//! The first search uses the config weight as the inflation epsilon, which
+//! finds a path quickly. Epsilon is then lowered a step at a time and the
+//! search carries on from where it left off. Cells that got cheaper after they
+//! were expanded are parked on an INCONS list and put back in the frontier for
+//! the next round instead of being expanded again straight away, which is what
+//! lets each round reuse the work of the last. Every path found is kept along
+//! with its suboptimality bound.
+//!
+//! Like `AStar` the search runs from the goal back toward the start.
+
+use std::f32;
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::planner::*;
+use super::frontier::IndexedHeap;
+
+/// How much epsilon drops between rounds
+const EPSILON_STEP: f32 = 0.5;
+
+/// A path found by one round of the search
+#[derive(Clone, Debug)]
+struct Solution {
+    epsilon: f32,
+    /// the path costs at most this many times the optimal cost
+    bound: f32,
+    cost: f32,
+    path: Vec<Id>,
+}
+
+#[derive(Clone)]
+pub struct AraStar {
+    config: AStarCfg,
+    current: Option<Id>,
+    frontier: IndexedHeap,
+    world: World,
+    prev_step: usize,
+    epsilon: f32,
+    closed: Vec<bool>,
+    incons: Vec<Id>,
+    solutions: Vec<Solution>,
+    selected: Option<usize>,
+    started: bool,
+    finished: bool,
+}
+
+impl AraStar {
+
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<AraStar, PlanningError> {
+
+        Algorithm::AraStar.valid_for(&cfg, &world)?;
+
+        let n = world.width() * world.height();
+        Ok(AraStar {
+            epsilon: cfg.weight,
+            config: cfg,
+            current: None,
+            frontier: IndexedHeap::new(n),
+            world,
+            prev_step: 0,
+            closed: vec![false; n],
+            incons: Vec::new(),
+            solutions: Vec::new(),
+            selected: None,
+            started: false,
+            finished: false,
+        })
+    }
+
+    fn visited(&self, id: Id) -> Option<(f32, f32, Id)> {
+        match self.world.cell(id) {
+            Some(Cell::Visited { g, h, k:_, parent }) => Some((*g, *h, *parent)),
+            _ => None,
+        }
+    }
+
+    fn cost_to_goal(&self, id: Id) -> f32 {
+        self.visited(id).map_or(f32::INFINITY, |(g, _, _)| g)
+    }
+
+    /// Record the path found by the round that just finished
+    fn publish(&mut self) {
+        let start = self.config.start.unwrap();
+        let goal = self.config.goal.unwrap();
+        let cost = self.cost_to_goal(start);
+
+        // the cheapest unexpanded cell limits how much better a path can be
+        let mut lower = f32::INFINITY;
+        for &id in self.frontier.ids().iter().chain(self.incons.iter()) {
+            if let Some((g, h, _)) = self.visited(id) {
+                lower = lower.min(g + h);
+            }
+        }
+        let bound = if lower == f32::INFINITY {
+            1.0
+        } else {
+            self.epsilon.min(cost / lower).max(1.0)
+        };
+
+        let mut path = vec![start];
+        let mut id = start;
+        while id != goal {
+            id = self.visited(id).unwrap().2;
+            path.push(id);
+        }
+        self.solutions.push(Solution { epsilon: self.epsilon, bound, cost, path });
+        self.selected = Some(self.solutions.len() - 1);
+    }
+
+    /// Lower epsilon and requeue everything for the next round
+    fn next_round(&mut self) {
+        self.epsilon = (self.epsilon - EPSILON_STEP).max(1.0);
+        let mut open = self.frontier.ids();
+        open.append(&mut self.incons);
+        self.frontier.clear();
+        for id in open {
+            let (g, h, _) = self.visited(id).unwrap();
+            let priority = g + self.epsilon * h;
+            self.frontier.push(id, priority);
+        }
+        for closed in self.closed.iter_mut() {
+            *closed = false;
+        }
+    }
+}
+
+impl Planner for AraStar {
+
+    fn box_clone(&self) -> Box<dyn Planner> {
+        Box::new(self.clone())
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::AraStar
+    }
+
+    fn current(&self) -> Option<Id> {
+        self.current
+    }
+
+    fn step(&mut self) -> StepResult {
+
+        if self.finished {
+            return StepResult::AlreadyFinished
+        }
+
+        let start = self.config.start.unwrap();
+        let goal = self.config.goal.unwrap();
+
+        // first time step is called, use goal
+        if !self.started {
+            self.started = true;
+            *self.world.cell_mut(goal).unwrap() =
+                Cell::Visited { g: 0.0, h: 0.0, k: 0.0, parent: goal };
+            self.frontier.push(goal, 0.0);
+        }
+
+        // a round is over once nothing in the frontier can beat the start
+        let start_cost = self.cost_to_goal(start);
+        let round_over = match self.frontier.peek() {
+            Some((_, f)) => f >= start_cost,
+            None => true,
+        };
+        if round_over {
+            if start_cost == f32::INFINITY {
+                // no path at all
+                self.finished = true;
+                return StepResult::NoPath { step: self.prev_step }
+            }
+            self.publish();
+            if self.solutions.last().unwrap().bound <= 1.0 {
+                self.finished = true;
+                self.current = Some(start);
+                return StepResult::Found { step: self.prev_step, cost: start_cost }
+            }
+            // the next round reopens the whole frontier at once
+            self.next_round();
+            self.prev_step += 1;
+            return StepResult::Expanded {
+                step: self.prev_step,
+                cell: start,
+                opened: self.frontier.ids().len(),
+            }
+        }
+
+        let (next, _) = self.frontier.pop().unwrap();
+        self.closed[next] = true;
+        self.current = Some(next);
+
+        let my_coord = self.world.coords_for(next).unwrap();
+        let start_coord = self.world.coords_for(start).unwrap();
+        let my_cost = self.cost_to_goal(next);
+
+        let neighbors = self.world.iter_neighbor_ids(next, self.config.neighbors).unwrap();
+        let mut opened = 0;
+        for (x,y) in neighbors {
+            let id = match self.world.id_at(x,y) {
+                Some(id) => id,
+                None => continue,
+            };
+            if let Some(Cell::Obstacle) = self.world.cell(id) { continue };
+            let new_cost = my_cost + self.config.edge_cost(&self.world, (x,y), my_coord);
+            if new_cost >= self.cost_to_goal(id) { continue };
+
+            let new_heur = self.config.estimate((x,y), start_coord);
+            *self.world.cell_mut(id).unwrap() =
+                Cell::Visited { g: new_cost, h: new_heur, k: 0.0, parent: next };
+            if !self.closed[id] {
+                let priority = new_cost + self.epsilon * new_heur;
+                self.frontier.push(id, priority);
+                opened += 1;
+            } else if !self.incons.contains(&id) {
+                // already expanded this round, revisit it next round
+                self.incons.push(id);
+            }
+        }
+
+        self.prev_step += 1;
+        StepResult::Expanded { step: self.prev_step, cell: next, opened }
+    }
+
+    fn world_view(&self) -> &World {
+        &self.world
+    }
+
+    fn config_view(&self) -> &AStarCfg {
+        &self.config
+    }
+
+    fn frontier_view(&self) -> Frontier {
+        self.frontier.sorted()
+    }
+
+    fn path(&self) -> Option<Vec<Id>> {
+        self.selected.map(|idx| self.solutions[idx].path.clone())
+    }
+
+    fn highlights(&self) -> Vec<(Id, Highlight)> {
+        self.incons.iter().map(|&id| (id, Highlight::Inconsistent)).collect()
+    }
+
+    fn stats(&self) -> Vec<String> {
+        let mut stats = vec![
+            format!("Expansions: {}", self.prev_step),
+            format!("Epsilon: {:0.1}", self.epsilon),
+        ];
+        for (idx, solution) in self.solutions.iter().enumerate() {
+            stats.push(format!("{}{}. eps {:0.1} cost {:0.1} <= {:0.2}x",
+                               if Some(idx) == self.selected { ">" } else { " " },
+                               idx + 1, solution.epsilon, solution.cost, solution.bound));
+        }
+        stats
+    }
+
+    fn solution_count(&self) -> usize {
+        self.solutions.len()
+    }
+
+    fn selected_solution(&self) -> Option<usize> {
+        self.selected
+    }
+
+    fn select_solution(&mut self, idx: usize) {
+        if idx < self.solutions.len() {
+            self.selected = Some(idx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn solutions_improve_to_optimal() {
+        let mut cells = vec![Cell::Open; 144];
+        for y in 0..9 {
+            cells[y * 12 + 4] = Cell::Obstacle;
+        }
+        for y in 3..12 {
+            cells[y * 12 + 8] = Cell::Obstacle;
+        }
+        let w = World::new(12, 12, cells).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,0).unwrap())
+                    .with_start(w.id_at(11,11).unwrap())
+                    .with_hueristic(Some(Heuristic::Euclidean))
+                    .with_neighbors(Neighbors::CardinalAndDiagonal)
+                    .with_weight(3.0);
+
+        let mut ara = AraStar::from_cfg(cfg.clone(), w.clone()).unwrap();
+        while ara.step().expanded() {}
+        let mut astar = AStar::from_cfg(cfg.with_weight(1.0), w).unwrap();
+        while astar.step().expanded() {}
+        let optimal = match astar.world_view().cell(astar.start()) {
+            Some(Cell::Visited { g, h:_, k:_, parent:_ }) => *g,
+            _ => panic!("start was not visited"),
+        };
+
+        assert!(ara.solution_count() > 1);
+        for pair in ara.solutions.windows(2) {
+            assert!(pair[1].cost <= pair[0].cost + 1e-4);
+            assert!(pair[1].epsilon < pair[0].epsilon);
+        }
+        for solution in ara.solutions.iter() {
+            assert!(solution.cost <= solution.bound * optimal + 1e-4);
+        }
+        let last = ara.solutions.last().unwrap();
+        assert!((last.cost - optimal).abs() < 1e-4);
+        assert_eq!(ara.selected_solution(), Some(ara.solution_count() - 1));
+
+        ara.select_solution(0);
+        assert_eq!(ara.path().unwrap(), ara.solutions[0].path);
+    }
+
+    #[test]
+    fn needs_inflated_heuristic() {
+        let w = World::new(4, 4, vec![Cell::Open; 16]).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,0).unwrap())
+                    .with_start(w.id_at(3,3).unwrap());
+        assert!(AraStar::from_cfg(cfg, w).is_err());
+    }
+}
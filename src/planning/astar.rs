@@ -1,9 +1,132 @@
+use std::fmt;
+use std::sync::Arc;
+use std::str::FromStr;
+
 use super::world::*;
+use super::planner::*;
+use super::error::PlanningError;
+use super::frontier::IndexedHeap;
 
-#[derive(Debug, Clone)]
+/// Heuristic estimate between two cell coordinates
+pub type HeuristicFn = Arc<dyn Fn((usize, usize), (usize, usize)) -> f32 + Send + Sync>;
+
+#[derive(Clone)]
 pub enum Heuristic {
     Manhattan,
     Euclidean,
+    /// Exact distance on an open 8-connected grid
+    Octile,
+    /// Number of king moves, ignoring that diagonals cost more
+    Chebyshev,
+    /// Supplied by the user, shared so configs can still be cloned
+    Custom(HeuristicFn),
+}
+
+impl fmt::Debug for Heuristic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Heuristic::Custom(_) => write!(f, "Custom"),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+impl fmt::Display for Heuristic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Heuristic::Manhattan => write!(f, "Manhattan"),
+            Heuristic::Euclidean => write!(f, "Euclidean"),
+            Heuristic::Octile    => write!(f, "Octile"),
+            Heuristic::Chebyshev => write!(f, "Chebyshev"),
+            Heuristic::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+/// Parse the name of a built in heuristic, ignoring case
+impl FromStr for Heuristic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Heuristic, String> {
+        match s.to_lowercase().as_str() {
+            "manhattan" => Ok(Heuristic::Manhattan),
+            "euclidean" => Ok(Heuristic::Euclidean),
+            "octile"    => Ok(Heuristic::Octile),
+            "chebyshev" => Ok(Heuristic::Chebyshev),
+            _ => Err(format!("unknown heuristic '{}'", s)),
+        }
+    }
+}
+
+impl Heuristic {
+    /// The built in heuristic after this one, cycling through no heuristic
+    pub fn next(h: &Option<Heuristic>) -> Option<Heuristic> {
+        match h {
+            None                       => Some(Heuristic::Euclidean),
+            Some(Heuristic::Euclidean) => Some(Heuristic::Manhattan),
+            Some(Heuristic::Manhattan) => Some(Heuristic::Octile),
+            Some(Heuristic::Octile)    => Some(Heuristic::Chebyshev),
+            Some(Heuristic::Chebyshev) => None,
+            Some(Heuristic::Custom(_)) => None,
+        }
+    }
+}
+
+/// Which cell to expand first when several have the same f
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TieBreak {
+    /// closest to the target by the heuristic
+    LowerH,
+    /// furthest from where the search began
+    HigherG,
+    /// the cell queued or updated earliest
+    Fifo,
+    /// the cell queued or updated most recently
+    Lifo,
+    /// a pseudo random order that repeats for the same seed
+    Random(u64),
+}
+
+impl fmt::Display for TieBreak {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TieBreak::LowerH    => write!(f, "Lower h"),
+            TieBreak::HigherG   => write!(f, "Higher g"),
+            TieBreak::Fifo      => write!(f, "FIFO"),
+            TieBreak::Lifo      => write!(f, "LIFO"),
+            TieBreak::Random(s) => write!(f, "Random ({})", s),
+        }
+    }
+}
+
+impl TieBreak {
+    pub fn next(self) -> TieBreak {
+        match self {
+            TieBreak::Lifo      => TieBreak::Fifo,
+            TieBreak::Fifo      => TieBreak::LowerH,
+            TieBreak::LowerH    => TieBreak::HigherG,
+            TieBreak::HigherG   => TieBreak::Random(1),
+            TieBreak::Random(_) => TieBreak::Lifo,
+        }
+    }
+}
+
+/// Which end of the path the search grows from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// from the goal toward the start, g is the cost to the goal
+    Backward,
+    /// from the start toward the goal, g is the cost from the start
+    Forward,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Backward => write!(f, "Backward"),
+            Direction::Forward  => write!(f, "Forward"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -12,17 +135,41 @@ pub struct AStarCfg {
     pub heuristic: Option<Heuristic>,
     pub goal: Option<Id>,
     pub start: Option<Id>,
+    /// Heuristic inflation, A* ranks cells by f = g + weight * h
+    pub weight: f32,
+    /// Order of cells with equal f
+    pub tie_break: TieBreak,
+    /// End of the path `AStar` searches from
+    pub direction: Direction,
 }
 
-type Frontier = Vec<(Id, f32)>;
+/// How cells are ranked in the frontier
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FrontierOrder {
+    /// f = g + h
+    Cost,
+    /// f = h, ignoring the cost so far
+    Heuristic,
+    /// first in first out, cells are never reopened
+    Fifo,
+}
 
 #[derive(Clone)]
 pub struct AStar {
     config: AStarCfg,
     current: Option<Id>,
-    frontier: Frontier, // cell Id, cost
+    frontier: IndexedHeap,
     world: World,
     prev_step: usize,
+    order: FrontierOrder,
+    pushed: u64,
+    finished: bool,
+    /// what the last step did
+    events: Vec<StepEvent>,
+    /// state of the generator for random tie breaking
+    rng: u64,
+    /// largest inflation seen over the cells reached so far
+    max_inflation: f32,
 }
 
 impl AStarCfg {
@@ -33,66 +180,161 @@ impl AStarCfg {
             heuristic: None,
             goal: None,
             start: None,
+            weight: 1.0,
+            tie_break: TieBreak::Lifo,
+            direction: Direction::Backward,
         }
     }
 
     pub fn with_goal(self, id: Id) -> AStarCfg {
         AStarCfg {
-            neighbors: self.neighbors,
-            heuristic: self.heuristic,
             goal: Some(id),
-            start: self.start,
+            ..self
         }
     }
 
     pub fn with_start(self, id: Id) -> AStarCfg {
         AStarCfg {
-            neighbors: self.neighbors,
-            heuristic: self.heuristic,
-            goal: self.goal,
             start: Some(id),
+            ..self
         }
     }
 
     pub fn with_hueristic(self, h: Option<Heuristic>) -> AStarCfg {
         AStarCfg {
-            neighbors: self.neighbors,
             heuristic: h,
-            goal: self.goal,
-            start: self.start,
+            ..self
         }
     }
 
     pub fn with_neighbors(self, n: Neighbors) -> AStarCfg {
         AStarCfg {
             neighbors: n,
-            heuristic: self.heuristic,
-            goal: self.goal,
-            start: self.start,
+            ..self
         }
     }
 
-    pub fn valid_for(&self, world: &World) -> Result<(),String> {
+    /// Use a closure estimating the cost between two cell coordinates
+    pub fn with_custom_heuristic<F>(self, h: F) -> AStarCfg
+        where F: Fn((usize, usize), (usize, usize)) -> f32 + Send + Sync + 'static
+    {
+        AStarCfg {
+            heuristic: Some(Heuristic::Custom(Arc::new(h))),
+            ..self
+        }
+    }
 
-        if let Some(goal) = self.goal {
-            if None == world.coords_for(goal) {
-                return Err("Invalid goal".to_string());
-            }
-        } else {
-            return Err("Must specify goal point".to_string());
+    pub fn with_weight(self, w: f32) -> AStarCfg {
+        AStarCfg {
+            weight: w,
+            ..self
         }
+    }
 
-        if let Some(start) = self.start {
-            if None == world.coords_for(start) {
-                return Err("Invalid start".to_string());
-            }
-        } else {
-            return Err("Must specify start point".to_string());
+    pub fn with_tie_break(self, t: TieBreak) -> AStarCfg {
+        AStarCfg {
+            tie_break: t,
+            ..self
+        }
+    }
+
+    pub fn with_direction(self, d: Direction) -> AStarCfg {
+        AStarCfg {
+            direction: d,
+            ..self
+        }
+    }
+
+    /// Cell the search grows from
+    pub fn root(&self) -> Option<Id> {
+        match self.direction {
+            Direction::Backward => self.goal,
+            Direction::Forward => self.start,
+        }
+    }
+
+    /// Cell the search is trying to reach and the heuristic aims at
+    pub fn target(&self) -> Option<Id> {
+        match self.direction {
+            Direction::Backward => self.start,
+            Direction::Forward => self.goal,
+        }
+    }
+
+    pub fn valid_for(&self, world: &World) -> Result<(), PlanningError> {
+
+        if self.weight.is_nan() || self.weight < 1.0 {
+            return Err(PlanningError::InvalidWeight(self.weight));
+        }
+
+        let goal = self.goal.ok_or(PlanningError::MissingGoal)?;
+        match world.cell(goal) {
+            None => return Err(PlanningError::GoalOutOfBounds(goal)),
+            Some(Cell::Obstacle) => return Err(PlanningError::GoalBlocked(goal)),
+            _ => {}
+        }
+
+        let start = self.start.ok_or(PlanningError::MissingStart)?;
+        match world.cell(start) {
+            None => return Err(PlanningError::StartOutOfBounds(start)),
+            Some(Cell::Obstacle) => return Err(PlanningError::StartBlocked(start)),
+            _ => {}
         }
 
         Ok(())
     }
 
+    /// Cost of moving between two neighboring cells
+    pub fn step_cost(&self, a: (usize, usize), b: (usize, usize)) -> f32 {
+        match self.neighbors {
+            Neighbors::CardinalAndDiagonal => calc_euclidean_dist(a, b),
+            Neighbors::Cardinal => 1.0,
+        }
+    }
+
+    /// Cost of moving between two neighboring cells across the world's terrain
+    pub fn edge_cost(&self, world: &World, a: (usize, usize), b: (usize, usize)) -> f32 {
+        self.step_cost(a, b) * world.terrain_factor(a, b)
+    }
+
+    /// Heuristic estimate of the cost between two cells
+    pub fn estimate(&self, a: (usize, usize), b: (usize, usize)) -> f32 {
+        match &self.heuristic {
+            Some(Heuristic::Euclidean) => calc_euclidean_dist(a, b),
+            Some(Heuristic::Manhattan) => calc_manhattan_dist(a, b) as f32,
+            Some(Heuristic::Octile)    => calc_octile_dist(a, b),
+            Some(Heuristic::Chebyshev) => calc_chebyshev_dist(a, b) as f32,
+            Some(Heuristic::Custom(h)) => h(a, b),
+            None => 0.0,
+        }
+    }
+
+    /// Cost between two cells on an empty all road world, the most an
+    /// admissible heuristic can estimate
+    pub fn open_cost(&self, a: (usize, usize), b: (usize, usize)) -> f32 {
+        match self.neighbors {
+            Neighbors::CardinalAndDiagonal => calc_octile_dist(a, b),
+            Neighbors::Cardinal => calc_manhattan_dist(a, b) as f32,
+        }
+    }
+
+    /// How many times the weighted estimate exceeds the open world cost,
+    /// above 1 the search may return a path that is not optimal
+    pub fn inflation(&self, a: (usize, usize), b: (usize, usize)) -> Option<f32> {
+        let open = self.open_cost(a, b);
+        if open == 0.0 {
+            None
+        } else {
+            Some(self.weight * self.estimate(a, b) / open)
+        }
+    }
+
+}
+
+impl Default for AStarCfg {
+    fn default() -> AStarCfg {
+        AStarCfg::new()
+    }
 }
 
 fn calc_euclidean_dist(a: (usize, usize), b: (usize, usize)) -> f32 {
@@ -108,74 +350,151 @@ fn calc_manhattan_dist(a: (usize, usize), b: (usize, usize)) -> usize {
     (ax.max(bx) - ax.min(bx)) + (ay.max(by) - ay.min(by))
 }
 
+fn calc_chebyshev_dist(a: (usize, usize), b: (usize, usize)) -> usize {
+    let (ax, ay) = a;
+    let (bx, by) = b;
+    (ax.max(bx) - ax.min(bx)).max(ay.max(by) - ay.min(by))
+}
+
+fn calc_octile_dist(a: (usize, usize), b: (usize, usize)) -> f32 {
+    let (ax, ay) = a;
+    let (bx, by) = b;
+    let dx = (ax.max(bx) - ax.min(bx)) as f32;
+    let dy = (ay.max(by) - ay.min(by)) as f32;
+    dx.max(dy) + (2.0f32.sqrt() - 1.0) * dx.min(dy)
+}
+
 impl AStar {
 
-    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<AStar, String> {
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<AStar, PlanningError> {
+        AStar::with_order(cfg, world, FrontierOrder::Cost)
+    }
 
+    pub(crate) fn with_order(cfg: AStarCfg, world: World, order: FrontierOrder)
+        -> Result<AStar, PlanningError>
+    {
         cfg.valid_for(&world)?;
 
         Ok(AStar {
+            rng: match cfg.tie_break { TieBreak::Random(seed) => seed, _ => 0 },
             config: cfg,
             current: None,
-            frontier: Vec::new(),
-            world: world,
+            frontier: IndexedHeap::new(world.width() * world.height()),
+            world,
             prev_step: 0,
+            order,
+            pushed: 0,
+            finished: false,
+            events: Vec::new(),
+            max_inflation: 0.0,
         })
     }
 
-    pub fn start(&self) -> Id {
-        self.config.start.unwrap()
+    /// Largest weighted estimate over the open world cost seen so far, above
+    /// 1 the heuristic has overestimated somewhere
+    pub fn max_inflation(&self) -> f32 {
+        self.max_inflation
+    }
+
+    /// Frontier priority of a cell `moves` steps from the root
+    fn priority(&mut self, g: f32, h: f32, moves: f32) -> f32 {
+        self.pushed += 1;
+        match self.order {
+            FrontierOrder::Cost => g + self.config.weight * h,
+            FrontierOrder::Heuristic => h,
+            // first in first out leaves the queue sorted by moves already
+            FrontierOrder::Fifo => moves,
+        }
+    }
+
+    /// Tie key and sequence number ordering cells of equal priority, lowest first
+    fn tie(&mut self, g: f32, h: f32) -> (f32, u64) {
+        if self.order == FrontierOrder::Fifo {
+            return (0.0, self.pushed)
+        }
+        match self.config.tie_break {
+            TieBreak::LowerH => (h, 0),
+            TieBreak::HigherG => (-g, 0),
+            TieBreak::Fifo => (0.0, self.pushed),
+            TieBreak::Lifo => (0.0, u64::MAX - self.pushed),
+            TieBreak::Random(_) => {
+                // 64 bit linear congruential generator from Knuth's MMIX
+                self.rng = self.rng.wrapping_mul(6364136223846793005)
+                                   .wrapping_add(1442695040888963407);
+                ((self.rng >> 40) as f32, 0)
+            }
+        }
     }
+}
+
+impl Planner for AStar {
 
-    pub fn goal(&self) -> Id {
-        self.config.goal.unwrap()
+    fn box_clone(&self) -> Box<dyn Planner> {
+        Box::new(self.clone())
     }
 
-    pub fn current(&self) -> Option<Id> {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::AStar
+    }
+
+    fn current(&self) -> Option<Id> {
         self.current
     }
 
-    pub fn step(&mut self) -> Option<usize> {
+    fn step(&mut self) -> StepResult {
+
+        let root = self.config.root().unwrap();
+        let target = self.config.target().unwrap();
 
         // check if done
-        if let Some(c) = self.current {
-            if c == self.config.start.unwrap() { 
-                return None 
-            };
-        };
+        if self.finished {
+            return StepResult::AlreadyFinished
+        }
+        self.events.clear();
 
-        let goal = self.config.goal.unwrap();
-        // get the next cell
-        let next: Id = match self.current {
-            // first time step is called, use goal
+        // get the next cell and its frontier priority
+        let (next, next_f): (Id, f32) = match self.current {
+            // first time step is called, use the root
             None => {
-                *self.world.cell_mut(goal).unwrap() =
+                *self.world.cell_mut(root).unwrap() =
                     Cell::Visited {
                         g: 0.0,
                         h: 0.0,
                         k: 0.0,
-                        parent: goal
+                        parent: root
                     };
-                goal
+                self.events.push(StepEvent::Popped { cell: root, f: 0.0 });
+                (root, 0.0)
             }
             Some(_id) => {
                 // pull the best from the frontier
-                if let Some((id,_)) = self.frontier.pop() { id }
-                // empty frontier? search was already completed
-                else { return None }
+                if let Some((id, f)) = self.frontier.pop() {
+                    self.events.push(StepEvent::Popped { cell: id, f });
+                    (id, f)
+                }
+                // empty frontier? nothing left that could reach the target
+                else {
+                    self.finished = true;
+                    return StepResult::NoPath { step: self.prev_step }
+                }
             }
         };
 
         // check if done
-        if next == self.config.start.unwrap() { 
+        if next == target { 
             self.current = Some(next);
-            return None
+            self.finished = true;
+            let cost = match *self.world.cell(next).unwrap() {
+                Cell::Visited { g, h:_, k:_, parent:_ } => g,
+                _ => 0.0f32
+            };
+            return StepResult::Found { step: self.prev_step, cost }
         };
 
         // get x, y coordinates for current cell
         let my_coord = self.world.coords_for(next).unwrap();
         // get x, y coordinates for current goal
-        let goal_coord = self.world.coords_for(self.config.start.unwrap()).unwrap();
+        let goal_coord = self.world.coords_for(target).unwrap();
 
         let my_cost = match *self.world.cell(next).unwrap() {
             Cell::Visited { g, h:_, k:_, parent:_ } => g,
@@ -186,26 +505,27 @@ impl AStar {
         let neighbors =
             self.world.iter_neighbor_ids(next, self.config.neighbors).unwrap();
 
+        // breadth first never revisits a cell once it has been reached
+        let reopen = self.order != FrontierOrder::Fifo;
+        let mut opened = 0;
+
         for (x,y) in neighbors {
             // a way to signal that we need to add to frontier after updates
-            let mut add_to_frontier: Option<f32> = None;
+            let mut add_to_frontier: Option<(f32, f32)> = None;
+            // determine cost to go
+            let new_cost = self.config.edge_cost(&self.world, (x,y), my_coord) + my_cost;
+            let id = match self.world.id_at(x,y) { Some(id) => id, None => continue };
             if let Some(cell) = self.world.cell_at_mut(x,y) {
                 // skip obstacles
-                if let Cell::Obstacle = cell { continue };
-                // determine cost to go
-                let new_cost = match self.config.neighbors {
-                    Neighbors::CardinalAndDiagonal =>
-                        calc_euclidean_dist((x,y), my_coord),
-                    Neighbors::Cardinal => 1.0,
-                } + my_cost;
-                // determine heuristic
-                let new_heur = match self.config.heuristic {
-                    Some(Heuristic::Euclidean) =>
-                        calc_euclidean_dist((x,y), goal_coord),
-                    Some(Heuristic::Manhattan) =>
-                        calc_manhattan_dist((x,y), goal_coord) as f32,
-                    None => 0.0,
+                if let Cell::Obstacle = cell {
+                    self.events.push(StepEvent::Blocked { cell: id });
+                    continue
                 };
+                // determine heuristic
+                let new_heur = self.config.estimate((x,y), goal_coord);
+                if let Some(inflation) = self.config.inflation((x,y), goal_coord) {
+                    self.max_inflation = self.max_inflation.max(inflation);
+                }
                 // build Cell data - optionally used below
                 let new_cell = Cell::Visited {
                     g: new_cost,
@@ -217,66 +537,76 @@ impl AStar {
                 match *cell {
                     // if visited then we already have it in the frontier, 
                     // just update
-                    Cell::Visited { g, h:_, k:_, parent:_ } =>
-                        if g > new_cost {
-                            add_to_frontier = Some(new_heur + new_cost);
-                            *cell = new_cell;
-                        },
+                    Cell::Visited { g, h:_, k:_, parent:_ } if reopen && g > new_cost => {
+                        add_to_frontier = Some((new_cost, new_heur));
+                        *cell = new_cell;
+                        self.events.push(StepEvent::Improved { cell: id, old_g: g, new_g: new_cost });
+                    },
                     // if open then its unvisited and needs to be added to the
                     // frontier list and updated
                     Cell::Open => {
-                        add_to_frontier = Some(new_heur + new_cost);
+                        add_to_frontier = Some((new_cost, new_heur));
                         *cell = new_cell;
+                        self.events.push(StepEvent::Opened { cell: id, g: new_cost, h: new_heur });
                     },
                     // this match arm should never hit
                     _ => { },
                 };
             }
             // replace or add to frontier
-            if let Some(cost) = add_to_frontier {
-                let id = self.world.id_at(x,y).unwrap();
-                let location = self.frontier.iter().position(
-                    |&(i,_)| i == id
-                );
-                match location {
-                    Some(idx) => self.frontier[idx] = (id, cost),
-                    None      => self.frontier.push((id, cost))
-                };
+            if let Some((g, h)) = add_to_frontier {
+                let cost = self.priority(g, h, next_f + 1.0);
+                let (tie, seq) = self.tie(g, h);
+                self.frontier.push_keyed(id, cost, tie, seq);
+                opened += 1;
             }
         }
 
-        self.frontier.sort_by(|a, b| {
-            let (_, cost_a) = a;
-            let (_, cost_b) = b;
-            cost_b.partial_cmp(cost_a).unwrap()
-        });
         self.prev_step += 1;
         self.current = Some(next);
-        Some(self.prev_step)
+        StepResult::Expanded { step: self.prev_step, cell: next, opened }
+    }
+
+    fn events(&self) -> &[StepEvent] {
+        &self.events
     }
 
-    pub fn world_view(&self) -> &World {
+    fn world_view(&self) -> &World {
         &self.world
     }
 
-    pub fn config_view(&self) -> &AStarCfg {
+    fn config_view(&self) -> &AStarCfg {
         &self.config
     }
 
-    pub fn frontier_view(&self) -> &Frontier {
-        &self.frontier
+    fn frontier_view(&self) -> Frontier {
+        self.frontier.sorted()
+    }
+
+    fn stats(&self) -> Vec<String> {
+        let mut stats = vec![format!("Expansions: {}", self.prev_step)];
+        if self.order == FrontierOrder::Cost && self.config.weight != 1.0
+            && self.config.heuristic.is_some() {
+            // holds as long as the heuristic never overestimates
+            stats.push(format!("Weight {:0.1}: cost <= {:0.1} x optimal",
+                               self.config.weight, self.config.weight));
+        }
+        if self.config.heuristic.is_some() {
+            stats.push(format!("Heuristic inflation: {:0.2}x", self.max_inflation));
+        }
+        stats
     }
 
-    pub fn path(&self) -> Option<Vec<Id>> {
+    fn path(&self) -> Option<Vec<Id>> {
         // check if done
         if let Some(c) = self.current {
-            if c != self.config.start.unwrap() { 
+            if c != self.config.target().unwrap() { 
                 None
             } else {
-                let mut path = vec![c];
+                let mut path = Vec::new();
                 let mut prev_id = c;
-                let goal = self.config.goal.unwrap();
-                while prev_id != goal {
+                let root = self.config.root().unwrap();
+                while prev_id != root {
                     if let Some(Cell::Visited{g:_,h:_,k:_,parent}) = self.world.cell(prev_id) {
                         path.push(prev_id);
                         prev_id = *parent;
@@ -284,7 +614,11 @@ impl AStar {
                         panic!("State corruption");
                     }
                 }
-                path.push(goal);
+                path.push(root);
+                // parents lead back to the root, always report start to goal
+                if self.config.direction == Direction::Forward {
+                    path.reverse();
+                }
                 Some(path)
             }
         } else {
@@ -297,6 +631,7 @@ impl AStar {
 mod tests {
 
     use super::*;
+    use super::super::map::Map;
 
     #[test]
     fn test_dist_funcs() {
@@ -309,47 +644,301 @@ mod tests {
         assert!(calc_euclidean_dist(
             (1,1),(0,0))-(2.0f32).sqrt().abs() < 1e-6
         );
+        assert_eq!(calc_chebyshev_dist((0,0),(3,1)), 3);
+        assert_eq!(calc_chebyshev_dist((3,1),(0,0)), 3);
+        assert!((calc_octile_dist((0,0),(3,1)) - (2.0 + 2.0f32.sqrt())).abs() < 1e-6);
+        assert!((calc_octile_dist((2,5),(2,1)) - 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_heuristic_inflation() {
+
+        let w = World::new(8, 8, vec![Cell::Open; 64]).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,0).unwrap())
+                    .with_start(w.id_at(7,3).unwrap())
+                    .with_neighbors(Neighbors::CardinalAndDiagonal);
+
+        let run = |cfg: AStarCfg| {
+            let mut astar = AStar::from_cfg(cfg, w.clone()).unwrap();
+            while astar.step().expanded() {}
+            assert!(astar.path().is_some());
+            astar.max_inflation()
+        };
+
+        // octile is exact on an open map, manhattan overestimates diagonals
+        let octile = cfg.clone().with_hueristic(Some(Heuristic::Octile));
+        assert!((run(octile.clone()) - 1.0).abs() < 1e-4);
+        assert!(run(cfg.clone().with_hueristic(Some(Heuristic::Chebyshev))) <= 1.0);
+        assert!(run(cfg.clone().with_hueristic(Some(Heuristic::Manhattan))) > 1.0);
+        assert!((run(octile.with_weight(2.0)) - 2.0).abs() < 1e-4);
+
+        let custom = cfg.with_custom_heuristic(|a, b| 3.0 * calc_chebyshev_dist(a, b) as f32);
+        assert_eq!(custom.estimate((0,0), (2,1)), 6.0);
+        assert!(run(custom.clone()) > 2.0);
+        assert_eq!(format!("{:?}", custom.heuristic), "Some(Custom)");
+    }
+
+    #[test]
+    fn test_thread_safe() {
+        // library users may hand searches to other threads
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<AStarCfg>();
+        is_send_sync::<AStar>();
+        is_send_sync::<World>();
+    }
+
+    #[test]
+    fn test_path_visits_start_once() {
+        let w = World::new(3, 1, vec![Cell::Open; 3]).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(0)
+                    .with_start(2)
+                    .with_neighbors(Neighbors::Cardinal);
+        let mut astar = AStar::from_cfg(cfg, w).unwrap();
+        while astar.step().expanded() {}
+        assert_eq!(astar.path(), Some(vec![2, 1, 0]));
     }
 
     #[test]
     fn test_simple_pathing() {
 
-        let w = World::new( 4, 8, vec![
-            Cell::Open, Cell::Obstacle, Cell::Open, Cell::Open,
-            Cell::Open, Cell::Obstacle, Cell::Open, Cell::Open,
-            Cell::Open, Cell::Open,     Cell::Open, Cell::Open,
-            Cell::Open, Cell::Open,     Cell::Open, Cell::Open,
-            Cell::Open, Cell::Open,     Cell::Open, Cell::Open,
-            Cell::Open, Cell::Open,     Cell::Open, Cell::Open,
-            Cell::Open, Cell::Open,     Cell::Open, Cell::Open,
-            Cell::Open, Cell::Open,     Cell::Open, Cell::Open,
-        ]).unwrap();
+        let map: Map = "
+            G#.S
+            .#..
+            ....
+            ....
+            ....
+            ....
+            ....
+            ....
+        ".parse().unwrap();
 
         let cfg = AStarCfg::new()
-                    .with_goal(w.id_at(0,0).unwrap())
-                    .with_start(w.id_at(3,0).unwrap())
+                    .with_goal(map.goal.unwrap())
+                    .with_start(map.start.unwrap())
                     .with_hueristic(Some(Heuristic::Euclidean))
                     .with_neighbors(Neighbors::Cardinal);
 
         println!("{:#?}",cfg);
 
-        let mut astar = AStar::from_cfg(cfg, w).unwrap();
+        let mut astar = AStar::from_cfg(cfg, map.world.clone()).unwrap();
 
         let mut last_step = 0;
         loop {
             println!("Step {}",last_step);
             match astar.step() {
-                Some(step) => last_step = step,
-                None => break
+                StepResult::Expanded { step, .. } => last_step = step,
+                _ => break
             };
         }
 
-        let wv = astar.world_view();
-        for i in 0..wv.height() {
-            for j in 0..wv.width() {
-                print!("{}\t\t",wv.cell_at(j,i).unwrap());
+        let solved = Map { world: astar.world_view().clone(), path: astar.path().unwrap(), ..map };
+        println!("{}", solved);
+        assert_eq!(solved.to_string(), "\
+            G#*S\n\
+            *#*o\n\
+            ***o\n\
+            ooo.\n\
+            ....\n\
+            ....\n\
+            ....\n\
+            ....\n");
+    }
+
+    #[test]
+    fn test_tie_breaking() {
+
+        let w = World::new(20, 20, vec![Cell::Open; 400]).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,0).unwrap())
+                    .with_start(w.id_at(19,13).unwrap())
+                    .with_hueristic(Some(Heuristic::Octile))
+                    .with_neighbors(Neighbors::CardinalAndDiagonal);
+
+        let run = |t: TieBreak| {
+            let mut astar = AStar::from_cfg(cfg.clone().with_tie_break(t), w.clone()).unwrap();
+            let mut expanded = Vec::new();
+            while astar.step().expanded() { expanded.push(astar.current().unwrap()) }
+            let start = astar.path().unwrap()[0];
+            match astar.world_view().cell(start) {
+                Some(Cell::Visited { g, h:_, k:_, parent:_ }) => (expanded, *g),
+                _ => panic!("start was not visited"),
+            }
+        };
+
+        let policies = [TieBreak::LowerH, TieBreak::HigherG, TieBreak::Fifo,
+                        TieBreak::Lifo, TieBreak::Random(7)];
+        let mut counts = Vec::new();
+        for &t in policies.iter() {
+            let (expanded, cost) = run(t);
+            assert!((cost - (13.0 * 2.0f32.sqrt() + 6.0)).abs() < 1e-4);
+            // the same policy always expands the same cells
+            assert_eq!(expanded, run(t).0);
+            println!("{}: {} expansions", t, expanded.len());
+            counts.push(expanded.len());
+        }
+        // on an open map preferring cells near the target heads straight there
+        assert!(counts.iter().all(|&c| c >= counts[0]));
+        assert!(counts[2] > 2 * counts[0]);
+        assert_ne!(run(TieBreak::Random(7)).0, run(TieBreak::Random(8)).0);
+    }
+
+    #[test]
+    fn test_search_direction() {
+
+        let w: World = "
+            ...#......
+            ...#......
+            ...#......
+            ...#..#...
+            ...#..#...
+            ...#..#...
+            ...#..#...
+            ......#...
+            ......#...
+            ......#...
+        ".parse().unwrap();
+
+        for &n in [Neighbors::Cardinal, Neighbors::CardinalAndDiagonal].iter() {
+            let cfg = AStarCfg::new()
+                        .with_goal(w.id_at(1,8).unwrap())
+                        .with_start(w.id_at(9,1).unwrap())
+                        .with_hueristic(Some(Heuristic::Octile))
+                        .with_neighbors(n);
+            let mut costs = Vec::new();
+            for &d in [Direction::Backward, Direction::Forward].iter() {
+                let mut astar = AStar::from_cfg(cfg.clone().with_direction(d), w.clone()).unwrap();
+                while astar.step().expanded() {}
+                let path = astar.path().unwrap();
+                assert_eq!(*path.first().unwrap(), astar.start());
+                assert_eq!(*path.last().unwrap(), astar.goal());
+                assert_eq!(astar.current(), astar.config_view().target());
+                costs.push(path_cost(&astar));
+            }
+            assert!((costs[0] - costs[1]).abs() < 1e-4);
+        }
+
+        let forward = AStarCfg::new()
+                        .with_goal(0)
+                        .with_start(99)
+                        .with_direction(Direction::Forward);
+        assert!(Algorithm::Dijkstra.valid_for(&forward, &w).is_ok());
+        assert!(Algorithm::DStarLite.valid_for(&forward, &w).is_err());
+    }
+
+    #[test]
+    fn test_weighted_bound() {
+
+        let mut cells = vec![Cell::Open; 144];
+        for y in 1..11 {
+            cells[y * 12 + 6] = Cell::Obstacle;
+        }
+        let w = World::new(12, 12, cells).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,5).unwrap())
+                    .with_start(w.id_at(11,6).unwrap())
+                    .with_hueristic(Some(Heuristic::Euclidean))
+                    .with_neighbors(Neighbors::CardinalAndDiagonal);
+
+        let run = |weight: f32| {
+            let mut astar = AStar::from_cfg(cfg.clone().with_weight(weight), w.clone()).unwrap();
+            let mut expanded = 0;
+            while astar.step().expanded() { expanded += 1 }
+            let start = astar.path().unwrap()[0];
+            match astar.world_view().cell(start) {
+                Some(Cell::Visited { g, h:_, k:_, parent:_ }) => (expanded, *g),
+                _ => panic!("start was not visited"),
             }
-            println!("");
+        };
+
+        let (optimal_expanded, optimal) = run(1.0);
+        let (weighted_expanded, weighted) = run(3.0);
+        assert!(weighted >= optimal - 1e-4);
+        assert!(weighted <= 3.0 * optimal);
+        assert!(weighted_expanded < optimal_expanded);
+        // no heuristic to inflate, so no bound to report
+        let unweighted = AStar::from_cfg(cfg.clone().with_weight(3.0).with_hueristic(None), w.clone());
+        assert!(unweighted.unwrap().stats().iter().all(|line| !line.starts_with("Weight")));
+        assert!(AStar::from_cfg(cfg.with_weight(0.5), w).is_err());
+    }
+
+    fn step_events(astar: &mut AStar) -> Vec<Vec<StepEvent>> {
+        let mut steps = Vec::new();
+        while astar.step() != StepResult::AlreadyFinished {
+            steps.push(astar.events().to_vec());
+        }
+        steps
+    }
+
+    #[test]
+    fn test_step_events() {
+        use planning::planner::StepEvent::*;
+
+        let mut w = World::new(3, 2, vec![Cell::Open; 6]).unwrap();
+        *w.cell_at_mut(1,1).unwrap() = Cell::Obstacle;
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,0).unwrap())
+                    .with_start(w.id_at(2,0).unwrap())
+                    .with_hueristic(Some(Heuristic::Manhattan));
+        let mut astar = AStar::from_cfg(cfg, w).unwrap();
+        assert_eq!(step_events(&mut astar), vec![
+            vec![Popped { cell: 0, f: 0.0 },
+                 Opened { cell: 1, g: 1.0, h: 1.0 },
+                 Opened { cell: 3, g: 1.0, h: 3.0 }],
+            vec![Popped { cell: 1, f: 2.0 },
+                 Opened { cell: 2, g: 2.0, h: 0.0 },
+                 Blocked { cell: 4 }],
+            vec![Popped { cell: 2, f: 2.0 }],
+        ]);
+
+        // grass first reached through the mud, then more cheaply around it
+        let mut w = World::new(3, 2, vec![Cell::Open; 6]).unwrap();
+        w.set_terrain(1, Terrain::Mud);
+        w.set_terrain(2, Terrain::Grass);
+        let mut astar = AStar::from_cfg(AStarCfg::new().with_goal(0).with_start(2), w).unwrap();
+        assert_eq!(step_events(&mut astar), vec![
+            vec![Popped { cell: 0, f: 0.0 },
+                 Opened { cell: 1, g: 2.5, h: 0.0 },
+                 Opened { cell: 3, g: 1.0, h: 0.0 }],
+            vec![Popped { cell: 3, f: 1.0 }, Opened { cell: 4, g: 2.0, h: 0.0 }],
+            vec![Popped { cell: 4, f: 2.0 }, Opened { cell: 5, g: 3.0, h: 0.0 }],
+            vec![Popped { cell: 1, f: 2.5 }, Opened { cell: 2, g: 5.5, h: 0.0 }],
+            vec![Popped { cell: 5, f: 3.0 }, Improved { cell: 2, old_g: 5.5, new_g: 4.5 }],
+            vec![Popped { cell: 2, f: 4.5 }],
+        ]);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let mut w = World::new(4, 4, vec![Cell::Open; 16]).unwrap();
+        *w.cell_at_mut(1,1).unwrap() = Cell::Obstacle;
+        let err = |cfg: AStarCfg| AStar::from_cfg(cfg, w.clone()).err();
+
+        assert_eq!(err(AStarCfg::new().with_start(0)), Some(PlanningError::MissingGoal));
+        assert_eq!(err(AStarCfg::new().with_goal(0)), Some(PlanningError::MissingStart));
+        let cfg = AStarCfg::new().with_goal(0).with_start(15);
+        assert!(err(cfg.clone()).is_none());
+        assert_eq!(err(cfg.clone().with_goal(16)), Some(PlanningError::GoalOutOfBounds(16)));
+        assert_eq!(err(cfg.clone().with_start(99)), Some(PlanningError::StartOutOfBounds(99)));
+        assert_eq!(err(cfg.clone().with_goal(5)), Some(PlanningError::GoalBlocked(5)));
+        assert_eq!(err(cfg.clone().with_start(5)), Some(PlanningError::StartBlocked(5)));
+        assert_eq!(err(cfg.clone().with_weight(0.5)), Some(PlanningError::InvalidWeight(0.5)));
+        assert_eq!(Algorithm::JumpPoint.valid_for(&cfg, &w),
+                   Err(PlanningError::Unsupported { algorithm: Algorithm::JumpPoint,
+                                                    reason: "requires diagonal moves" }));
+        assert_eq!(Algorithm::ThetaStar.valid_for(&cfg.clone().with_weight(2.0), &w),
+                   Err(PlanningError::Unsupported { algorithm: Algorithm::ThetaStar,
+                                                    reason: "ignores the heuristic weight" }));
+        for algorithm in [Algorithm::Dijkstra, Algorithm::BreadthFirst, Algorithm::GreedyBestFirst,
+                          Algorithm::DStarLite, Algorithm::LpaStar, Algorithm::IdaStar].iter() {
+            assert!(algorithm.build(cfg.clone().with_weight(2.0), w.clone()).is_err());
         }
+        assert!(Algorithm::AStar.valid_for(&cfg.clone().with_weight(2.0), &w).is_ok());
+        let forward = cfg.with_direction(Direction::Forward);
+        assert_eq!(format!("{}", Algorithm::DStarLite.valid_for(&forward, &w).unwrap_err()),
+                   "D* Lite only searches backward from the goal");
+        // building checks the same rules rather than quietly searching backward
+        assert!(Algorithm::DStarLite.build(forward, w.clone()).is_err());
     }
 }
@@ -0,0 +1,70 @@
+//! Breadth-first search, expanding cells in the order they were reached.
+//!
+//! The frontier priority is the number of moves from the root, and cells the
+//! same number of moves out leave in the order they were queued. Step costs
+//! and the heuristic play no part in the search, so the path is only optimal
+//! when every move costs the same.
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::planner::*;
+
+#[derive(Clone)]
+pub struct BreadthFirst {
+    astar: AStar,
+}
+
+impl BreadthFirst {
+
+    /// Any heuristic in the config is ignored
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<BreadthFirst, PlanningError> {
+        Ok(BreadthFirst {
+            astar: AStar::with_order(cfg.with_hueristic(None), world, FrontierOrder::Fifo)?,
+        })
+    }
+}
+
+impl Planner for BreadthFirst {
+
+    fn box_clone(&self) -> Box<dyn Planner> { Box::new(self.clone()) }
+
+    fn algorithm(&self) -> Algorithm { Algorithm::BreadthFirst }
+
+    fn step(&mut self) -> StepResult { self.astar.step() }
+
+    fn current(&self) -> Option<Id> { self.astar.current() }
+
+    fn events(&self) -> &[StepEvent] { self.astar.events() }
+
+    fn frontier_view(&self) -> Frontier { self.astar.frontier_view() }
+
+    fn path(&self) -> Option<Vec<Id>> { self.astar.path() }
+
+    fn world_view(&self) -> &World { self.astar.world_view() }
+
+    fn config_view(&self) -> &AStarCfg { self.astar.config_view() }
+
+    fn stats(&self) -> Vec<String> { self.astar.stats() }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn queues_by_moves_then_arrival() {
+        let w = World::new(5, 1, vec![Cell::Open; 5]).unwrap();
+        let cfg = AStarCfg::new()
+            .with_goal(2)
+            .with_start(4)
+            .with_neighbors(Neighbors::Cardinal)
+            .with_tie_break(TieBreak::Lifo);
+        let mut bfs = BreadthFirst::from_cfg(cfg, w).unwrap();
+        bfs.step();
+        assert_eq!(bfs.frontier_view(), vec![(3, 1.0), (1, 1.0)]);
+        bfs.step();
+        assert_eq!(bfs.frontier_view(), vec![(1, 1.0), (4, 2.0)]);
+    }
+}
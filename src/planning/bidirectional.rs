@@ -0,0 +1,299 @@
+//! Bidirectional A*, growing one search from the start and one from the goal.
+//!
+//! The two searches take turns expanding a cell. Whenever one of them reaches
+//! a cell the other has already reached, the combined cost through that cell
+//! is a candidate path. The best candidate is only accepted once the cheapest
+//! f value left in either frontier is no better, since every remaining path
+//! must pass through both frontiers.
+
+use std::f32;
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::planner::*;
+use super::frontier::IndexedHeap;
+
+/// State for one direction of the search
+#[derive(Clone)]
+struct Side {
+    /// cell the search grows from
+    root: Id,
+    /// cell the heuristic aims at
+    target: Id,
+    g: Vec<f32>,
+    parent: Vec<Id>,
+    frontier: IndexedHeap,
+}
+
+impl Side {
+
+    fn new(root: Id, target: Id, n: usize) -> Side {
+        let mut g = vec![f32::INFINITY; n];
+        g[root] = 0.0;
+        let mut frontier = IndexedHeap::new(n);
+        frontier.push(root, 0.0);
+        Side {
+            root,
+            target,
+            g,
+            parent: vec![root; n],
+            frontier,
+        }
+    }
+
+    /// Smallest f value waiting in the frontier
+    fn top(&self) -> f32 {
+        self.frontier.peek().map_or(f32::INFINITY, |(_, cost)| cost)
+    }
+
+    /// Parents from a cell back to the root of this side
+    fn trace(&self, from: Id) -> Vec<Id> {
+        let mut ids = vec![from];
+        let mut id = from;
+        while id != self.root {
+            id = self.parent[id];
+            ids.push(id);
+        }
+        ids
+    }
+}
+
+#[derive(Clone)]
+pub struct BidirectionalAStar {
+    config: AStarCfg,
+    current: Option<Id>,
+    world: World,
+    prev_step: usize,
+    forward: Side,
+    backward: Side,
+    forward_turn: bool,
+    /// cost of the best path found so far
+    best: f32,
+    /// cell where the two searches joined on the best path
+    meeting: Option<Id>,
+    finished: bool,
+}
+
+impl BidirectionalAStar {
+
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<BidirectionalAStar, PlanningError> {
+
+        Algorithm::Bidirectional.valid_for(&cfg, &world)?;
+
+        let n = world.width() * world.height();
+        let start = cfg.start.unwrap();
+        let goal = cfg.goal.unwrap();
+        let mut world = world;
+        for &id in [start, goal].iter() {
+            *world.cell_mut(id).unwrap() =
+                Cell::Visited { g: 0.0, h: 0.0, k: 0.0, parent: id };
+        }
+        Ok(BidirectionalAStar {
+            config: cfg,
+            current: None,
+            world,
+            prev_step: 0,
+            forward: Side::new(start, goal, n),
+            backward: Side::new(goal, start, n),
+            forward_turn: true,
+            best: if start == goal { 0.0 } else { f32::INFINITY },
+            meeting: if start == goal { Some(start) } else { None },
+            finished: false,
+        })
+    }
+
+    /// Expand the best cell on one side, returning how many neighbors it
+    /// opened or None if it had nothing left
+    fn expand(&mut self, forward: bool) -> Option<usize> {
+        let (side, other) = if forward {
+            (&mut self.forward, &self.backward)
+        } else {
+            (&mut self.backward, &self.forward)
+        };
+        let next = match side.frontier.pop() {
+            Some((id, _)) => id,
+            None => return None,
+        };
+        self.current = Some(next);
+
+        let my_coord = self.world.coords_for(next).unwrap();
+        let target_coord = self.world.coords_for(side.target).unwrap();
+        let neighbors = self.world.iter_neighbor_ids(next, self.config.neighbors).unwrap();
+        let mut opened = 0;
+
+        for (x,y) in neighbors {
+            let id = match self.world.id_at(x,y) {
+                Some(id) => id,
+                None => continue,
+            };
+            let new_cost = side.g[next] + self.config.edge_cost(&self.world, (x,y), my_coord);
+            let cell = self.world.cell_mut(id).unwrap();
+            if let Cell::Obstacle = cell { continue };
+            if new_cost >= side.g[id] { continue };
+
+            let new_heur = self.config.estimate((x,y), target_coord);
+            side.g[id] = new_cost;
+            side.parent[id] = next;
+            *cell = Cell::Visited { g: new_cost, h: new_heur, k: 0.0, parent: next };
+            let cost = new_cost + new_heur;
+            side.frontier.push(id, cost);
+            opened += 1;
+
+            // reached by both searches, a candidate path
+            if new_cost + other.g[id] < self.best {
+                self.best = new_cost + other.g[id];
+                self.meeting = Some(id);
+            }
+        }
+
+        Some(opened)
+    }
+}
+
+impl Planner for BidirectionalAStar {
+
+    fn box_clone(&self) -> Box<dyn Planner> {
+        Box::new(self.clone())
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Bidirectional
+    }
+
+    fn current(&self) -> Option<Id> {
+        self.current
+    }
+
+    fn step(&mut self) -> StepResult {
+
+        if self.finished {
+            return StepResult::AlreadyFinished
+        }
+
+        // nothing left to find that beats the best path
+        if self.meeting.is_some() &&
+            (self.forward.top() >= self.best || self.backward.top() >= self.best)
+        {
+            self.finished = true;
+            return StepResult::Found { step: self.prev_step, cost: self.best }
+        }
+
+        let forward = self.forward_turn;
+        self.forward_turn = !self.forward_turn;
+        let opened = match self.expand(forward).or_else(|| self.expand(!forward)) {
+            Some(opened) => opened,
+            None => {
+                // both frontiers empty
+                self.finished = true;
+                return match self.meeting {
+                    Some(_) => StepResult::Found { step: self.prev_step, cost: self.best },
+                    None    => StepResult::NoPath { step: self.prev_step },
+                }
+            }
+        };
+
+        self.prev_step += 1;
+        StepResult::Expanded { step: self.prev_step, cell: self.current.unwrap(), opened }
+    }
+
+    fn world_view(&self) -> &World {
+        &self.world
+    }
+
+    fn config_view(&self) -> &AStarCfg {
+        &self.config
+    }
+
+    fn frontier_view(&self) -> Frontier {
+        let mut frontier = self.forward.frontier.sorted();
+        frontier.extend(self.backward.frontier.sorted());
+        frontier.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        frontier
+    }
+
+    fn path(&self) -> Option<Vec<Id>> {
+        if !self.finished {
+            return None
+        }
+        let meeting = self.meeting?;
+        let mut path = self.forward.trace(meeting);
+        path.reverse();
+        path.extend(self.backward.trace(meeting).into_iter().skip(1));
+        Some(path)
+    }
+
+    fn highlights(&self) -> Vec<(Id, Highlight)> {
+        let mut highlights = Vec::new();
+        for id in 0..self.forward.g.len() {
+            if self.forward.g[id] < f32::INFINITY {
+                highlights.push((id, Highlight::ForwardVisited));
+            }
+            if self.backward.g[id] < f32::INFINITY {
+                highlights.push((id, Highlight::BackwardVisited));
+            }
+        }
+        if let Some(id) = self.meeting {
+            highlights.push((id, Highlight::Meeting));
+        }
+        highlights
+    }
+
+    fn stats(&self) -> Vec<String> {
+        let mut stats = vec![format!("Expansions: {}", self.prev_step)];
+        if let Some(id) = self.meeting {
+            let (x,y) = self.world.coords_for(id).unwrap();
+            stats.push(format!("Best meeting: ({},{}) cost: {:0.1}", x, y, self.best));
+        }
+        stats.push(format!("Forward min f: {:0.1}", self.forward.top()));
+        stats.push(format!("Backward min f: {:0.1}", self.backward.top()));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn matches_astar() {
+        let mut cells = vec![Cell::Open; 100];
+        for &(x, y) in [(3,0), (3,1), (3,2), (3,3), (3,4), (3,5), (3,6),
+                        (6,3), (6,4), (6,5), (6,6), (6,7), (6,8), (6,9)].iter() {
+            cells[y * 10 + x] = Cell::Obstacle;
+        }
+        let w = World::new(10, 10, cells).unwrap();
+        for &n in [Neighbors::Cardinal, Neighbors::CardinalAndDiagonal].iter() {
+            let cfg = AStarCfg::new()
+                        .with_goal(w.id_at(0,0).unwrap())
+                        .with_start(w.id_at(9,9).unwrap())
+                        .with_hueristic(Some(Heuristic::Euclidean))
+                        .with_neighbors(n);
+            let mut bidir = BidirectionalAStar::from_cfg(cfg.clone(), w.clone()).unwrap();
+            let mut astar = AStar::from_cfg(cfg, w.clone()).unwrap();
+            run_search(&mut bidir);
+            run_search(&mut astar);
+            let path = bidir.path().unwrap();
+            assert_eq!(*path.first().unwrap(), bidir.start());
+            assert_eq!(*path.last().unwrap(), bidir.goal());
+            assert!(path.contains(&bidir.meeting.unwrap()));
+            assert!((path_cost(&bidir) - path_cost(&astar)).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn no_path() {
+        let mut cells = vec![Cell::Open; 16];
+        for y in 0..4 {
+            cells[y * 4 + 2] = Cell::Obstacle;
+        }
+        let w = World::new(4, 4, cells).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,0).unwrap())
+                    .with_start(w.id_at(3,3).unwrap());
+        let mut bidir = BidirectionalAStar::from_cfg(cfg, w).unwrap();
+        run_search(&mut bidir);
+        assert!(bidir.path().is_none());
+    }
+}
@@ -0,0 +1,44 @@
+//! Dijkstra's algorithm, expanding cells in order of cost so far only.
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::planner::*;
+
+#[derive(Clone)]
+pub struct Dijkstra {
+    astar: AStar,
+}
+
+impl Dijkstra {
+
+    /// Any heuristic in the config is ignored
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<Dijkstra, PlanningError> {
+        Ok(Dijkstra {
+            astar: AStar::from_cfg(cfg.with_hueristic(None), world)?,
+        })
+    }
+}
+
+impl Planner for Dijkstra {
+
+    fn box_clone(&self) -> Box<dyn Planner> { Box::new(self.clone()) }
+
+    fn algorithm(&self) -> Algorithm { Algorithm::Dijkstra }
+
+    fn step(&mut self) -> StepResult { self.astar.step() }
+
+    fn current(&self) -> Option<Id> { self.astar.current() }
+
+    fn events(&self) -> &[StepEvent] { self.astar.events() }
+
+    fn frontier_view(&self) -> Frontier { self.astar.frontier_view() }
+
+    fn path(&self) -> Option<Vec<Id>> { self.astar.path() }
+
+    fn world_view(&self) -> &World { self.astar.world_view() }
+
+    fn config_view(&self) -> &AStarCfg { self.astar.config_view() }
+
+    fn stats(&self) -> Vec<String> { self.astar.stats() }
+}
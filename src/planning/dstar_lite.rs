@@ -0,0 +1,371 @@
+//! D* Lite, an incremental search that repairs its earlier work when
+//! obstacles change instead of starting over.
+//!
+//! Like `AStar` the search runs from the goal back toward the start. Every
+//! cell keeps its cost to goal g and a one step lookahead rhs. Cells where the
+//! two disagree are locally inconsistent and are the only ones queued, so after
+//! an obstacle is toggled only the affected region gets expanded again.
+//!
+//! With a start that never moves this is Lifelong Planning A* run backwards,
+//! see `LpaStar` for the forward version.
+
+use std::f32;
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::frontier::IndexedHeap;
+use super::planner::*;
+
+/// Queue priority, compared lexicographically
+type Key = (f32, f32);
+
+#[derive(Clone)]
+pub struct DStarLite {
+    config: AStarCfg,
+    world: World,
+    g: Vec<f32>,
+    rhs: Vec<f32>,
+    parent: Vec<Id>,
+    /// keyed by k1 with k2 breaking ties
+    queue: IndexedHeap,
+    current: Option<Id>,
+    prev_step: usize,
+    started: bool,
+    finished: bool,
+    repairing: bool,
+    updated: Vec<Id>,
+    since_edit: usize,
+}
+
+impl DStarLite {
+
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<DStarLite, PlanningError> {
+
+        cfg.valid_for(&world)?;
+
+        let n = world.width() * world.height();
+        let goal = cfg.goal.unwrap();
+        Ok(DStarLite {
+            config: cfg,
+            world,
+            g: vec![f32::INFINITY; n],
+            rhs: vec![f32::INFINITY; n],
+            parent: vec![goal; n],
+            queue: IndexedHeap::new(n),
+            current: None,
+            prev_step: 0,
+            started: false,
+            finished: false,
+            repairing: false,
+            updated: Vec::new(),
+            since_edit: 0,
+        })
+    }
+
+    fn heuristic(&self, id: Id) -> f32 {
+        let start = self.config.start.unwrap();
+        self.config.estimate(self.world.coords_for(id).unwrap(),
+                             self.world.coords_for(start).unwrap())
+    }
+
+    fn calc_key(&self, id: Id) -> Key {
+        let m = self.g[id].min(self.rhs[id]);
+        (m + self.heuristic(id), m)
+    }
+
+    /// Cost to move between neighbors, infinite if either is blocked
+    fn cost(&self, a: Id, b: Id) -> f32 {
+        match (self.world.cell(a), self.world.cell(b)) {
+            (Some(Cell::Obstacle), _) | (_, Some(Cell::Obstacle)) => f32::INFINITY,
+            _ => self.config.edge_cost(&self.world,
+                                       self.world.coords_for(a).unwrap(),
+                                       self.world.coords_for(b).unwrap()),
+        }
+    }
+
+    fn neighbors(&self, id: Id) -> Vec<Id> {
+        self.world.iter_neighbor_ids(id, self.config.neighbors).unwrap()
+            .filter_map(|(x,y)| self.world.id_at(x,y))
+            .collect()
+    }
+
+    /// Copy the search state of a cell into the world for display
+    fn sync_cell(&mut self, id: Id) {
+        let (g, rhs) = (self.g[id], self.rhs[id]);
+        let new_cell = if g == f32::INFINITY && rhs == f32::INFINITY {
+            Cell::Open
+        } else {
+            Cell::Visited {
+                g,
+                h: self.heuristic(id),
+                k: self.calc_key(id).0,
+                parent: self.parent[id],
+            }
+        };
+        let cell = self.world.cell_mut(id).unwrap();
+        if *cell != Cell::Obstacle {
+            *cell = new_cell;
+        }
+    }
+
+    fn mark_updated(&mut self, id: Id) {
+        if self.repairing && !self.updated.contains(&id) {
+            self.updated.push(id);
+        }
+    }
+
+    /// Recompute the lookahead of a cell, true if it was queued as inconsistent
+    fn update_vertex(&mut self, id: Id) -> bool {
+        if id != self.config.goal.unwrap() {
+            // one step lookahead through the best neighbor
+            let mut best = (f32::INFINITY, self.parent[id]);
+            for n in self.neighbors(id) {
+                let cost = self.cost(id, n) + self.g[n];
+                if cost < best.0 {
+                    best = (cost, n);
+                }
+            }
+            if best.0 != self.rhs[id] || best.1 != self.parent[id] {
+                self.mark_updated(id);
+            }
+            self.rhs[id] = best.0;
+            self.parent[id] = best.1;
+        }
+        let queued = self.g[id] != self.rhs[id];
+        if queued {
+            let key = self.calc_key(id);
+            self.queue.push_tied(id, key.0, key.1);
+        } else {
+            self.queue.remove(id);
+        }
+        self.sync_cell(id);
+        queued
+    }
+
+    /// Cells whose g and rhs values currently disagree
+    pub fn inconsistent(&self) -> Vec<Id> {
+        self.queue.ids()
+    }
+}
+
+impl Planner for DStarLite {
+
+    fn box_clone(&self) -> Box<dyn Planner> {
+        Box::new(self.clone())
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::DStarLite
+    }
+
+    fn current(&self) -> Option<Id> {
+        self.current
+    }
+
+    fn step(&mut self) -> StepResult {
+
+        let goal = self.config.goal.unwrap();
+        let start = self.config.start.unwrap();
+
+        if self.finished {
+            return StepResult::AlreadyFinished
+        }
+
+        // first time step is called, seed the queue with the goal
+        if !self.started {
+            self.started = true;
+            self.rhs[goal] = 0.0;
+            self.parent[goal] = goal;
+            let key = self.calc_key(goal);
+            self.queue.push_tied(goal, key.0, key.1);
+            self.sync_cell(goal);
+        }
+
+        // done once the start is consistent and nothing better is queued
+        let (next, old_key) = match self.queue.peek_tied() {
+            Some((id, key)) if key < self.calc_key(start)
+                            || self.rhs[start] != self.g[start] => (id, key),
+            _ => {
+                self.finished = true;
+                return if self.g[start] == f32::INFINITY {
+                    StepResult::NoPath { step: self.prev_step }
+                } else {
+                    StepResult::Found { step: self.prev_step, cost: self.g[start] }
+                }
+            }
+        };
+
+        let new_key = self.calc_key(next);
+        let mut opened = 0;
+        if old_key < new_key {
+            // key is out of date, requeue
+            self.queue.push_tied(next, new_key.0, new_key.1);
+        } else if self.g[next] > self.rhs[next] {
+            // overconsistent, settle it and tell the neighbors
+            self.queue.remove(next);
+            self.g[next] = self.rhs[next];
+            self.sync_cell(next);
+            for n in self.neighbors(next) {
+                if self.update_vertex(n) { opened += 1 }
+            }
+        } else {
+            // underconsistent, forget it and recompute everything around it
+            self.queue.remove(next);
+            self.g[next] = f32::INFINITY;
+            for n in self.neighbors(next) {
+                if self.update_vertex(n) { opened += 1 }
+            }
+            if self.update_vertex(next) { opened += 1 }
+        }
+        self.mark_updated(next);
+
+        self.since_edit += 1;
+        self.prev_step += 1;
+        self.current = Some(next);
+        StepResult::Expanded { step: self.prev_step, cell: next, opened }
+    }
+
+    fn world_view(&self) -> &World {
+        &self.world
+    }
+
+    fn config_view(&self) -> &AStarCfg {
+        &self.config
+    }
+
+    fn frontier_view(&self) -> Frontier {
+        self.queue.sorted()
+    }
+
+    fn path(&self) -> Option<Vec<Id>> {
+        let start = self.config.start.unwrap();
+        let goal = self.config.goal.unwrap();
+        if !self.finished || self.g[start] == f32::INFINITY {
+            return None
+        }
+        let mut path = vec![start];
+        let mut id = start;
+        while id != goal {
+            id = self.parent[id];
+            path.push(id);
+            if path.len() > self.g.len() {
+                panic!("State corruption");
+            }
+        }
+        Some(path)
+    }
+
+    fn highlights(&self) -> Vec<(Id, Highlight)> {
+        self.updated.iter().map(|&id| (id, Highlight::Updated)).collect()
+    }
+
+    fn stats(&self) -> Vec<String> {
+        vec![
+            format!("Expansions: {}", self.prev_step),
+            format!("Since last edit: {}", self.since_edit),
+            format!("Inconsistent cells: {}", self.queue.len()),
+        ]
+    }
+
+    fn supports_replanning(&self) -> bool {
+        true
+    }
+
+    fn toggle_obstacle(&mut self, id: Id) -> bool {
+        if id == self.config.start.unwrap() || id == self.config.goal.unwrap() {
+            return false
+        }
+        match self.world.cell_mut(id) {
+            Some(cell) => *cell = match cell {
+                Cell::Obstacle => Cell::Open,
+                _              => Cell::Obstacle,
+            },
+            None => return false,
+        };
+
+        // every edge touching the cell changed cost
+        self.updated.clear();
+        self.since_edit = 0;
+        self.repairing = true;
+        self.finished = false;
+        if self.started {
+            for n in self.neighbors(id) {
+                self.update_vertex(n);
+            }
+            self.update_vertex(id);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn mkworld() -> World {
+        World::new(6, 6, vec![Cell::Open; 36]).unwrap()
+    }
+
+    fn mkcfg(w: &World) -> AStarCfg {
+        AStarCfg::new()
+            .with_goal(w.id_at(0,0).unwrap())
+            .with_start(w.id_at(5,5).unwrap())
+            .with_hueristic(Some(Heuristic::Euclidean))
+            .with_neighbors(Neighbors::CardinalAndDiagonal)
+    }
+
+    fn start_cost(planner: &dyn Planner) -> f32 {
+        match planner.world_view().cell(planner.start()) {
+            Some(Cell::Visited { g, h:_, k:_, parent:_ }) => *g,
+            _ => panic!("start was not visited"),
+        }
+    }
+
+    #[test]
+    fn repair_matches_fresh_search() {
+        let w = mkworld();
+        let cfg = mkcfg(&w);
+        let mut dstar = DStarLite::from_cfg(cfg.clone(), w.clone()).unwrap();
+        run_search(&mut dstar);
+
+        // block part of the straight line path
+        let mut blocked = w.clone();
+        for &(x,y) in [(2,2), (3,3), (2,3), (3,2)].iter() {
+            assert!(dstar.toggle_obstacle(w.id_at(x,y).unwrap()));
+            *blocked.cell_at_mut(x,y).unwrap() = Cell::Obstacle;
+        }
+        assert!(dstar.path().is_none());
+        let repair_steps = run_search(&mut dstar);
+        assert!(!dstar.highlights().is_empty());
+
+        let mut astar = AStar::from_cfg(cfg, blocked).unwrap();
+        run_search(&mut astar);
+        assert!((start_cost(&dstar) - start_cost(&astar)).abs() < 1e-4);
+        let path = dstar.path().unwrap();
+        assert_eq!(*path.first().unwrap(), dstar.start());
+        assert_eq!(*path.last().unwrap(), dstar.goal());
+        assert!(path.iter().all(|&id| *dstar.world_view().cell(id).unwrap() != Cell::Obstacle));
+        assert!(repair_steps > 0);
+    }
+
+    #[test]
+    fn removing_obstacle_restores_path() {
+        let w = mkworld();
+        let cfg = mkcfg(&w);
+        let mut dstar = DStarLite::from_cfg(cfg, w.clone()).unwrap();
+        run_search(&mut dstar);
+        let before = start_cost(&dstar);
+
+        let id = w.id_at(3,3).unwrap();
+        assert!(dstar.toggle_obstacle(id));
+        run_search(&mut dstar);
+        assert!(dstar.toggle_obstacle(id));
+        run_search(&mut dstar);
+        assert!((start_cost(&dstar) - before).abs() < 1e-4);
+
+        assert!(!dstar.toggle_obstacle(dstar.start()));
+        assert!(!dstar.toggle_obstacle(dstar.goal()));
+    }
+}
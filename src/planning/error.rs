@@ -0,0 +1,64 @@
+//! Reasons a world or search config can't be used for planning.
+
+use std::error::Error;
+use std::fmt;
+
+use super::world::Id;
+use super::planner::Algorithm;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlanningError {
+    /// Width times height does not match the number of cells given
+    SizeMismatch { width: usize, height: usize, cells: usize },
+    MissingStart,
+    MissingGoal,
+    StartOutOfBounds(Id),
+    GoalOutOfBounds(Id),
+    StartBlocked(Id),
+    GoalBlocked(Id),
+    /// Heuristic weight below 1 or not a number
+    InvalidWeight(f32),
+    /// The config is fine in general but this algorithm can't run with it
+    Unsupported { algorithm: Algorithm, reason: &'static str },
+    /// Map text with no rows
+    EmptyMap,
+    /// Map row with a different width than the first row
+    RaggedMap { line: usize, expected: usize, found: usize },
+    UnknownMapSymbol { line: usize, column: usize, symbol: char },
+    /// More than one start or goal marked on a map
+    DuplicateMarker(char),
+    /// Start, goal or path marked in text parsed as a `World`, which can't hold them
+    MarkerInWorld(char),
+    /// Map cells marked `*` that don't form one path from the start to the goal
+    BrokenPath,
+}
+
+impl fmt::Display for PlanningError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanningError::SizeMismatch { width, height, cells } =>
+                write!(f, "A {}x{} world needs {} cells but {} were given",
+                       width, height, width * height, cells),
+            PlanningError::MissingStart => write!(f, "Must specify start point"),
+            PlanningError::MissingGoal => write!(f, "Must specify goal point"),
+            PlanningError::StartOutOfBounds(id) => write!(f, "Start {} is outside the world", id),
+            PlanningError::GoalOutOfBounds(id) => write!(f, "Goal {} is outside the world", id),
+            PlanningError::StartBlocked(_) => write!(f, "Start is on an obstacle"),
+            PlanningError::GoalBlocked(_) => write!(f, "Goal is on an obstacle"),
+            PlanningError::InvalidWeight(w) =>
+                write!(f, "Heuristic weight must be at least 1, not {}", w),
+            PlanningError::Unsupported { algorithm, reason } => write!(f, "{} {}", algorithm, reason),
+            PlanningError::EmptyMap => write!(f, "Map has no rows"),
+            PlanningError::RaggedMap { line, expected, found } =>
+                write!(f, "Map line {} is {} cells wide, expected {}", line, found, expected),
+            PlanningError::UnknownMapSymbol { line, column, symbol } =>
+                write!(f, "Unknown map symbol '{}' at line {} column {}", symbol, line, column),
+            PlanningError::DuplicateMarker(marker) => write!(f, "Map marks '{}' more than once", marker),
+            PlanningError::MarkerInWorld(marker) =>
+                write!(f, "A world has no start, goal or path, parse text marking '{}' as a Map", marker),
+            PlanningError::BrokenPath => write!(f, "Map path marked with '*' doesn't lead from S to G"),
+        }
+    }
+}
+
+impl Error for PlanningError {}
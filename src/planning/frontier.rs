@@ -0,0 +1,364 @@
+//! Indexed priority queue used as the search frontier.
+//!
+//! A binary min-heap paired with a map from cell Id to heap slot, so the best
+//! cell can be popped in O(log n) and a cell already in the frontier can be
+//! found and re-prioritized in O(log n) instead of scanning for it.
+//!
+//! Cells with equal priority come out in order of a secondary tie key, lowest
+//! first, then of a sequence number given by the caller, and then in reverse
+//! order of when they first entered the frontier.
+
+use super::world::Id;
+use super::planner::Frontier;
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    id: Id,
+    priority: f32,
+    /// compared when priorities are equal
+    tie: f32,
+    /// compared when tie keys are equal, exact where a float count is not
+    seq: u64,
+    /// order the cell first entered the frontier, used to break remaining ties
+    entered: usize,
+}
+
+impl Entry {
+    /// True if this entry should come out of the frontier before the other
+    fn before(&self, other: &Entry) -> bool {
+        if self.priority != other.priority {
+            self.priority < other.priority
+        } else if self.tie != other.tie {
+            self.tie < other.tie
+        } else if self.seq != other.seq {
+            self.seq < other.seq
+        } else {
+            self.entered > other.entered
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IndexedHeap {
+    heap: Vec<Entry>,
+    /// heap slot for each cell Id, if it is in the frontier
+    slots: Vec<Option<usize>>,
+    pushed: usize,
+}
+
+impl IndexedHeap {
+
+    /// An empty frontier for Ids below `capacity`
+    pub fn new(capacity: usize) -> IndexedHeap {
+        IndexedHeap {
+            heap: Vec::new(),
+            slots: vec![None; capacity],
+            pushed: 0,
+        }
+    }
+
+    /// The best cell and its priority without removing it
+    pub fn peek(&self) -> Option<(Id, f32)> {
+        self.heap.first().map(|e| (e.id, e.priority))
+    }
+
+    /// The best cell with its priority and tie key, without removing it
+    pub fn peek_tied(&self) -> Option<(Id, (f32, f32))> {
+        self.heap.first().map(|e| (e.id, (e.priority, e.tie)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Add a cell, or change its priority if it is already queued
+    pub fn push(&mut self, id: Id, priority: f32) {
+        self.push_tied(id, priority, 0.0);
+    }
+
+    /// Add or update a cell with a key to order it among equal priorities
+    pub fn push_tied(&mut self, id: Id, priority: f32, tie: f32) {
+        self.push_keyed(id, priority, tie, 0);
+    }
+
+    /// Add or update a cell with a tie key and then a sequence number to
+    /// order it among equal priorities
+    pub fn push_keyed(&mut self, id: Id, priority: f32, tie: f32, seq: u64) {
+        match self.slots[id] {
+            Some(slot) => {
+                let old = self.heap[slot];
+                self.heap[slot].priority = priority;
+                self.heap[slot].tie = tie;
+                self.heap[slot].seq = seq;
+                if self.heap[slot].before(&old) {
+                    self.sift_up(slot);
+                } else {
+                    self.sift_down(slot);
+                }
+            }
+            None => {
+                self.pushed += 1;
+                self.heap.push(Entry { id, priority, tie, seq, entered: self.pushed });
+                let slot = self.heap.len() - 1;
+                self.slots[id] = Some(slot);
+                self.sift_up(slot);
+            }
+        }
+    }
+
+    /// Remove and return the best cell
+    pub fn pop(&mut self) -> Option<(Id, f32)> {
+        if self.heap.is_empty() {
+            return None
+        }
+        let last = self.heap.len() - 1;
+        self.swap(0, last);
+        let entry = self.heap.pop().unwrap();
+        self.slots[entry.id] = None;
+        if !self.heap.is_empty() {
+            self.sift_down(0);
+        }
+        Some((entry.id, entry.priority))
+    }
+
+    /// Take a cell out of the frontier, false if it wasn't queued
+    pub fn remove(&mut self, id: Id) -> bool {
+        let slot = match self.slots[id] {
+            Some(slot) => slot,
+            None => return false,
+        };
+        let last = self.heap.len() - 1;
+        self.swap(slot, last);
+        self.heap.pop();
+        self.slots[id] = None;
+        if slot < self.heap.len() {
+            // the moved entry may belong above or below its new slot
+            self.sift_up(slot);
+            self.sift_down(slot);
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        for entry in self.heap.iter() {
+            self.slots[entry.id] = None;
+        }
+        self.heap.clear();
+    }
+
+    /// Cells in no particular order
+    pub fn ids(&self) -> Vec<Id> {
+        self.heap.iter().map(|e| e.id).collect()
+    }
+
+    /// Every queued cell, best first
+    pub fn sorted(&self) -> Frontier {
+        let mut entries = self.heap.clone();
+        entries.sort_by(|a, b| {
+            a.priority.partial_cmp(&b.priority).unwrap()
+                .then(a.tie.partial_cmp(&b.tie).unwrap())
+                .then(a.seq.cmp(&b.seq))
+                .then(b.entered.cmp(&a.entered))
+        });
+        entries.iter().map(|e| (e.id, e.priority)).collect()
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.slots[self.heap[a].id] = Some(a);
+        self.slots[self.heap[b].id] = Some(b);
+    }
+
+    fn sift_up(&mut self, mut slot: usize) {
+        while slot > 0 {
+            let parent = (slot - 1) / 2;
+            if !self.heap[slot].before(&self.heap[parent]) {
+                break;
+            }
+            self.swap(slot, parent);
+            slot = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut slot: usize) {
+        loop {
+            let left = 2 * slot + 1;
+            let right = left + 1;
+            let mut best = slot;
+            if left < self.heap.len() && self.heap[left].before(&self.heap[best]) {
+                best = left;
+            }
+            if right < self.heap.len() && self.heap[right].before(&self.heap[best]) {
+                best = right;
+            }
+            if best == slot {
+                break;
+            }
+            self.swap(slot, best);
+            slot = best;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::time::Instant;
+    use planning::astar::*;
+    use planning::planner::Planner;
+    use planning::world::*;
+
+    #[test]
+    fn pops_in_priority_order() {
+        let mut uut = IndexedHeap::new(10);
+        for &(id, p) in [(3, 5.0), (1, 2.0), (7, 9.0), (4, 1.0), (0, 7.0)].iter() {
+            uut.push(id, p);
+        }
+        assert_eq!(uut.peek(), Some((4, 1.0)));
+        // decrease and increase existing keys
+        uut.push(7, 0.5);
+        uut.push(4, 8.0);
+        assert_eq!(uut.sorted(), vec![(7, 0.5), (1, 2.0), (3, 5.0), (0, 7.0), (4, 8.0)]);
+        let order: Vec<Id> = (0..5).map(|_| uut.pop().unwrap().0).collect();
+        assert_eq!(order, vec![7, 1, 3, 0, 4]);
+        assert_eq!(uut.pop(), None);
+        // popped cells can be queued again
+        uut.push(4, 3.0);
+        assert_eq!(uut.peek(), Some((4, 3.0)));
+    }
+
+    #[test]
+    fn removes_queued_cells() {
+        let mut uut = IndexedHeap::new(10);
+        for id in 0..8 {
+            uut.push(id, (id * 7 % 8) as f32);
+        }
+        assert!(uut.remove(0));
+        assert!(uut.remove(5));
+        assert!(!uut.remove(5));
+        assert!(!uut.remove(9));
+        assert_eq!(uut.len(), 6);
+        let order: Vec<Id> = (0..6).map(|_| uut.pop().unwrap().0).collect();
+        assert_eq!(order, vec![7, 6, 4, 3, 2, 1]);
+        assert!(uut.is_empty());
+    }
+
+    #[test]
+    fn ties_pop_newest_first() {
+        let mut uut = IndexedHeap::new(10);
+        for id in 0..5 {
+            uut.push(id, 1.0);
+        }
+        // updating keeps the original entry order
+        uut.push(2, 1.0);
+        let order: Vec<Id> = (0..5).map(|_| uut.pop().unwrap().0).collect();
+        assert_eq!(order, vec![4, 3, 2, 1, 0]);
+
+        // a tie key takes precedence over entry order
+        for &(id, tie) in [(0, 2.0), (1, 0.5), (2, 1.0), (3, -1.0)].iter() {
+            uut.push_tied(id, 1.0, tie);
+        }
+        uut.push_tied(0, 1.0, -2.0);
+        let order: Vec<Id> = (0..4).map(|_| uut.pop().unwrap().0).collect();
+        assert_eq!(order, vec![0, 3, 1, 2]);
+
+        // sequence numbers past where f32 can count still order exactly
+        let base = 1 << 40;
+        for id in 0..4 {
+            uut.push_keyed(id, 1.0, 0.0, base + 3 - id as u64);
+        }
+        uut.push_keyed(4, 1.0, -1.0, base + 9);
+        let order: Vec<Id> = (0..5).map(|_| uut.pop().unwrap().0).collect();
+        assert_eq!(order, vec![4, 3, 2, 1, 0]);
+    }
+
+    /// The frontier A* used before, a Vec re-sorted after every expansion
+    struct SortedVec(Vec<(Id, f32)>);
+
+    impl SortedVec {
+        fn push(&mut self, id: Id, priority: f32) {
+            match self.0.iter().position(|&(i,_)| i == id) {
+                Some(idx) => self.0[idx] = (id, priority),
+                None      => self.0.push((id, priority)),
+            };
+        }
+        fn pop(&mut self) -> Option<(Id, f32)> {
+            self.0.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+            self.0.pop()
+        }
+    }
+
+    /// Map of the given size with about a fifth of the cells blocked
+    fn generate_world(side: usize) -> World {
+        let mut seed: u32 = 12345;
+        let mut cells = Vec::with_capacity(side * side);
+        for _ in 0..side * side {
+            // a small linear congruential generator keeps the maps repeatable
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            cells.push(if (seed >> 16) % 5 == 0 { Cell::Obstacle } else { Cell::Open });
+        }
+        cells[0] = Cell::Open;
+        cells[side * side - 1] = Cell::Open;
+        World::new(side, side, cells).unwrap()
+    }
+
+    /// Corner to corner with no heuristic, so the frontier grows as large as it can
+    fn mkcfg(w: &World) -> AStarCfg {
+        AStarCfg::new()
+            .with_goal(0)
+            .with_start(w.width() * w.height() - 1)
+            .with_neighbors(Neighbors::CardinalAndDiagonal)
+    }
+
+    /// Replays the frontier operations of a search against the old frontier
+    fn time_sorted_vec(w: &World) -> f64 {
+        let cfg = mkcfg(w);
+        let start = cfg.start.unwrap();
+        let start_coord = w.coords_for(start).unwrap();
+        let mut g = vec![f32::INFINITY; w.width() * w.height()];
+        let mut frontier = SortedVec(Vec::new());
+        let timer = Instant::now();
+        g[0] = 0.0;
+        frontier.push(0, 0.0);
+        while let Some((id, _)) = frontier.pop() {
+            if id == start { break }
+            let coord = w.coords_for(id).unwrap();
+            for (x,y) in w.iter_neighbor_ids(id, cfg.neighbors).unwrap() {
+                let n = match w.id_at(x,y) { Some(n) => n, None => continue };
+                if let Some(Cell::Obstacle) = w.cell(n) { continue }
+                let cost = g[id] + cfg.step_cost(coord, (x,y));
+                if cost < g[n] {
+                    g[n] = cost;
+                    frontier.push(n, cost + cfg.estimate((x,y), start_coord));
+                }
+            }
+        }
+        timer.elapsed().as_secs_f64()
+    }
+
+    fn time_astar(w: &World) -> f64 {
+        let mut astar = AStar::from_cfg(mkcfg(w), w.clone()).unwrap();
+        let timer = Instant::now();
+        while astar.step().expanded() {}
+        assert!(astar.path().is_some());
+        timer.elapsed().as_secs_f64()
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_large_maps() {
+        for &side in [100, 300, 1000].iter() {
+            let w = generate_world(side);
+            let heap = time_astar(&w);
+            let sorted = time_sorted_vec(&w);
+            println!("{0}x{0}: heap {1:.3}s, sorted Vec {2:.3}s, {3:.1}x faster",
+                     side, heap, sorted, sorted / heap);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Greedy best-first search, expanding cells in order of heuristic only.
+//!
+//! Usually reaches the goal in few steps but the path is not guaranteed to
+//! be optimal.
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::planner::*;
+
+#[derive(Clone)]
+pub struct GreedyBestFirst {
+    astar: AStar,
+}
+
+impl GreedyBestFirst {
+
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<GreedyBestFirst, PlanningError> {
+        Ok(GreedyBestFirst {
+            astar: AStar::with_order(cfg, world, FrontierOrder::Heuristic)?,
+        })
+    }
+}
+
+impl Planner for GreedyBestFirst {
+
+    fn box_clone(&self) -> Box<dyn Planner> { Box::new(self.clone()) }
+
+    fn algorithm(&self) -> Algorithm { Algorithm::GreedyBestFirst }
+
+    fn step(&mut self) -> StepResult { self.astar.step() }
+
+    fn current(&self) -> Option<Id> { self.astar.current() }
+
+    fn events(&self) -> &[StepEvent] { self.astar.events() }
+
+    fn frontier_view(&self) -> Frontier { self.astar.frontier_view() }
+
+    fn path(&self) -> Option<Vec<Id>> { self.astar.path() }
+
+    fn world_view(&self) -> &World { self.astar.world_view() }
+
+    fn config_view(&self) -> &AStarCfg { self.astar.config_view() }
+
+    fn stats(&self) -> Vec<String> { self.astar.stats() }
+}
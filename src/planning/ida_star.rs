@@ -0,0 +1,252 @@
+//! Iterative Deepening A*, a depth-first search bounded by an f threshold.
+//!
+//! Instead of a frontier only the current branch is kept on a stack. Any cell
+//! whose f is over the threshold is cut off, and once the whole tree under the
+//! threshold has been explored the search starts over with the threshold
+//! raised to the smallest f that was cut off. Memory use is tiny at the price
+//! of expanding cells many times.
+//!
+//! Each step either goes one level deeper or backs out of a branch. Like
+//! `AStar` the search runs from the goal back toward the start.
+
+use std::f32;
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::planner::*;
+
+/// One level of the depth-first search
+#[derive(Clone)]
+struct Frame {
+    id: Id,
+    g: f32,
+    /// neighbors not tried yet
+    untried: Vec<Id>,
+}
+
+#[derive(Clone)]
+pub struct IdaStar {
+    config: AStarCfg,
+    current: Option<Id>,
+    world: World,
+    /// world before any cells were visited, restored for each iteration
+    blank: World,
+    prev_step: usize,
+    stack: Vec<Frame>,
+    threshold: f32,
+    next_threshold: f32,
+    iteration: usize,
+    found: bool,
+    finished: bool,
+}
+
+impl IdaStar {
+
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<IdaStar, PlanningError> {
+
+        Algorithm::IdaStar.valid_for(&cfg, &world)?;
+
+        let mut ida = IdaStar {
+            config: cfg,
+            current: None,
+            blank: world.clone(),
+            world,
+            prev_step: 0,
+            stack: Vec::new(),
+            threshold: 0.0,
+            next_threshold: f32::INFINITY,
+            iteration: 0,
+            found: false,
+            finished: false,
+        };
+        ida.threshold = ida.heuristic(ida.config.goal.unwrap());
+        Ok(ida)
+    }
+
+    fn heuristic(&self, id: Id) -> f32 {
+        self.config.estimate(self.world.coords_for(id).unwrap(),
+                             self.world.coords_for(self.config.start.unwrap()).unwrap())
+    }
+
+    fn push(&mut self, id: Id, g: f32) {
+        let parent = self.stack.last().map_or(id, |frame| frame.id);
+        let mut untried: Vec<Id> = self.world.iter_neighbor_ids(id, self.config.neighbors).unwrap()
+            .filter_map(|(x,y)| self.world.id_at(x,y))
+            .collect();
+        // pop from the back in the same order the neighbor iterator gives
+        untried.reverse();
+        *self.world.cell_mut(id).unwrap() =
+            Cell::Visited { g, h: self.heuristic(id), k: 0.0, parent };
+        self.stack.push(Frame { id, g, untried });
+        self.current = Some(id);
+    }
+}
+
+impl Planner for IdaStar {
+
+    fn box_clone(&self) -> Box<dyn Planner> {
+        Box::new(self.clone())
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::IdaStar
+    }
+
+    fn current(&self) -> Option<Id> {
+        self.current
+    }
+
+    fn step(&mut self) -> StepResult {
+
+        if self.finished {
+            return StepResult::AlreadyFinished
+        }
+
+        let mut opened = 0;
+
+        // start a new iteration from the goal
+        if self.stack.is_empty() {
+            self.world = self.blank.clone();
+            self.next_threshold = f32::INFINITY;
+            self.iteration += 1;
+            let goal = self.config.goal.unwrap();
+            self.push(goal, 0.0);
+            opened = 1;
+        } else {
+            let start_coord = self.world.coords_for(self.config.start.unwrap()).unwrap();
+            // try neighbors of the deepest cell until one is under the threshold
+            loop {
+                let (id, g, next) = {
+                    let frame = self.stack.last_mut().unwrap();
+                    (frame.id, frame.g, frame.untried.pop())
+                };
+                let next = match next {
+                    Some(next) => next,
+                    None => {
+                        // branch exhausted, back out
+                        self.stack.pop();
+                        self.current = self.stack.last().map(|frame| frame.id);
+                        if self.stack.is_empty() && self.next_threshold == f32::INFINITY {
+                            // nothing was cut off so there is no path
+                            self.finished = true;
+                            return StepResult::NoPath { step: self.prev_step }
+                        }
+                        if self.current.is_none() {
+                            self.current = Some(id);
+                        }
+                        if self.stack.is_empty() {
+                            self.threshold = self.next_threshold;
+                        }
+                        break;
+                    }
+                };
+                if let Some(Cell::Obstacle) = self.world.cell(next) { continue }
+                if self.stack.iter().any(|frame| frame.id == next) { continue }
+                let coord = self.world.coords_for(next).unwrap();
+                let new_cost = g + self.config.edge_cost(&self.world, coord,
+                                                         self.world.coords_for(id).unwrap());
+                let f = new_cost + self.config.estimate(coord, start_coord);
+                if f > self.threshold {
+                    self.next_threshold = self.next_threshold.min(f);
+                    continue
+                }
+                self.push(next, new_cost);
+                opened = 1;
+                break;
+            }
+        }
+
+        if self.stack.last().map(|frame| frame.id) == self.config.start {
+            self.found = true;
+            self.finished = true;
+            let cost = self.stack.last().unwrap().g;
+            return StepResult::Found { step: self.prev_step, cost }
+        }
+
+        self.prev_step += 1;
+        StepResult::Expanded { step: self.prev_step, cell: self.current.unwrap(), opened }
+    }
+
+    fn world_view(&self) -> &World {
+        &self.world
+    }
+
+    fn config_view(&self) -> &AStarCfg {
+        &self.config
+    }
+
+    /// The recursion stack, deepest cell first
+    fn frontier_view(&self) -> Frontier {
+        self.stack.iter().rev()
+            .map(|frame| (frame.id, frame.g + self.heuristic(frame.id)))
+            .collect()
+    }
+
+    fn frontier_title(&self) -> &'static str {
+        "Recursion stack:"
+    }
+
+    fn path(&self) -> Option<Vec<Id>> {
+        if !self.found {
+            return None
+        }
+        Some(self.stack.iter().rev().map(|frame| frame.id).collect())
+    }
+
+    fn highlights(&self) -> Vec<(Id, Highlight)> {
+        self.stack.iter().map(|frame| (frame.id, Highlight::OnStack)).collect()
+    }
+
+    fn stats(&self) -> Vec<String> {
+        vec![
+            format!("Steps: {}", self.prev_step),
+            format!("Iteration: {}", self.iteration),
+            format!("Threshold: {:0.1}", self.threshold),
+            format!("Next threshold: {:0.1}", self.next_threshold),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn mkcfg(w: &World) -> AStarCfg {
+        AStarCfg::new()
+            .with_goal(w.id_at(0,0).unwrap())
+            .with_start(w.id_at(4,4).unwrap())
+            .with_hueristic(Some(Heuristic::Manhattan))
+    }
+
+    #[test]
+    fn matches_astar() {
+        let mut cells = vec![Cell::Open; 25];
+        for &(x, y) in [(1,0), (1,1), (1,2), (3,2), (3,3), (3,4)].iter() {
+            cells[y * 5 + x] = Cell::Obstacle;
+        }
+        let w = World::new(5, 5, cells).unwrap();
+        let mut ida = IdaStar::from_cfg(mkcfg(&w), w.clone()).unwrap();
+        let mut astar = AStar::from_cfg(mkcfg(&w), w).unwrap();
+        while ida.step().expanded() {}
+        while astar.step().expanded() {}
+        let path = ida.path().unwrap();
+        assert_eq!(*path.first().unwrap(), ida.start());
+        assert_eq!(*path.last().unwrap(), ida.goal());
+        assert!((path_cost(&ida) - path_cost(&astar)).abs() < 1e-4);
+        assert!(ida.iteration > 1);
+    }
+
+    #[test]
+    fn no_path() {
+        let mut cells = vec![Cell::Open; 25];
+        for y in 0..5 {
+            cells[y * 5 + 2] = Cell::Obstacle;
+        }
+        let w = World::new(5, 5, cells).unwrap();
+        let mut ida = IdaStar::from_cfg(mkcfg(&w), w).unwrap();
+        while ida.step().expanded() {}
+        assert!(ida.path().is_none());
+    }
+}
@@ -0,0 +1,351 @@
+//! Jump Point Search, an A* variant for uniform cost 8-connected grids.
+//!
+//! Instead of adding every neighbor to the frontier it prunes the moves that
+//! some other equally short path already covers and then keeps moving in a
+//! straight line until it reaches a cell with a forced neighbor. Only those
+//! jump points are put in the frontier, which skips the large symmetric
+//! regions A* expands on open maps. Like `AStar` the search runs from the goal
+//! back toward the start.
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::planner::*;
+use super::frontier::IndexedHeap;
+
+/// A direction of travel, each component is -1, 0 or 1
+type Dir = (isize, isize);
+
+const ALL_DIRS: [Dir; 8] = [
+    (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1),
+];
+
+#[derive(Clone)]
+pub struct JumpPointSearch {
+    config: AStarCfg,
+    current: Option<Id>,
+    frontier: IndexedHeap,
+    world: World,
+    prev_step: usize,
+    pruned: Vec<Id>,
+    finished: bool,
+}
+
+impl JumpPointSearch {
+
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<JumpPointSearch, PlanningError> {
+
+        Algorithm::JumpPoint.valid_for(&cfg, &world)?;
+
+        Ok(JumpPointSearch {
+            config: cfg,
+            current: None,
+            frontier: IndexedHeap::new(world.width() * world.height()),
+            world,
+            prev_step: 0,
+            pruned: Vec::new(),
+            finished: false,
+        })
+    }
+
+    /// Outside the world or an obstacle
+    fn blocked(&self, x: isize, y: isize) -> bool {
+        if x < 0 || y < 0 {
+            return true
+        }
+        matches!(self.world.cell_at(x as usize, y as usize),
+                 Some(Cell::Obstacle) | None)
+    }
+
+    fn coords(&self, id: Id) -> (isize, isize) {
+        let (x, y) = self.world.coords_for(id).unwrap();
+        (x as isize, y as isize)
+    }
+
+    /// Moves that are forced by an obstacle beside the direction of travel
+    fn forced(&self, x: isize, y: isize, (dx, dy): Dir) -> Vec<Dir> {
+        let mut forced = Vec::new();
+        if dx != 0 && dy != 0 {
+            if self.blocked(x - dx, y) && !self.blocked(x - dx, y + dy) {
+                forced.push((-dx, dy));
+            }
+            if self.blocked(x, y - dy) && !self.blocked(x + dx, y - dy) {
+                forced.push((dx, -dy));
+            }
+        } else if dx != 0 {
+            for &side in [1, -1].iter() {
+                if self.blocked(x, y + side) && !self.blocked(x + dx, y + side) {
+                    forced.push((dx, side));
+                }
+            }
+        } else {
+            for &side in [1, -1].iter() {
+                if self.blocked(x + side, y) && !self.blocked(x + side, y + dy) {
+                    forced.push((side, dy));
+                }
+            }
+        }
+        forced
+    }
+
+    /// Directions worth exploring from a cell given how it was reached
+    fn successor_dirs(&self, id: Id) -> Vec<Dir> {
+        let parent = match self.world.cell(id) {
+            Some(Cell::Visited { g:_, h:_, k:_, parent }) => *parent,
+            _ => id,
+        };
+        if parent == id {
+            return ALL_DIRS.to_vec()
+        }
+        let (x, y) = self.coords(id);
+        let (px, py) = self.coords(parent);
+        let dir = ((x - px).signum(), (y - py).signum());
+        let mut dirs = if dir.0 != 0 && dir.1 != 0 {
+            vec![(dir.0, 0), (0, dir.1), dir]
+        } else {
+            vec![dir]
+        };
+        dirs.extend(self.forced(x, y, dir));
+        dirs
+    }
+
+    /// Travel from a cell in one direction until a jump point is found
+    fn jump(&self, x: isize, y: isize, (dx, dy): Dir) -> Option<(isize, isize)> {
+        let target = self.coords(self.config.start.unwrap());
+        let (mut x, mut y) = (x, y);
+        loop {
+            x += dx;
+            y += dy;
+            if self.blocked(x, y) {
+                return None
+            }
+            if (x, y) == target || !self.forced(x, y, (dx, dy)).is_empty() {
+                return Some((x, y))
+            }
+            // diagonal moves stop where a straight move would find something
+            if dx != 0 && dy != 0 &&
+                (self.jump(x, y, (dx, 0)).is_some() || self.jump(x, y, (0, dy)).is_some())
+            {
+                return Some((x, y))
+            }
+        }
+    }
+}
+
+impl Planner for JumpPointSearch {
+
+    fn box_clone(&self) -> Box<dyn Planner> {
+        Box::new(self.clone())
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::JumpPoint
+    }
+
+    fn current(&self) -> Option<Id> {
+        self.current
+    }
+
+    fn step(&mut self) -> StepResult {
+
+        let start = self.config.start.unwrap();
+        let goal = self.config.goal.unwrap();
+
+        // check if done
+        if self.finished {
+            return StepResult::AlreadyFinished
+        }
+
+        // get the next cell
+        let next: Id = match self.current {
+            // first time step is called, use goal
+            None => {
+                *self.world.cell_mut(goal).unwrap() =
+                    Cell::Visited { g: 0.0, h: 0.0, k: 0.0, parent: goal };
+                goal
+            }
+            Some(_) => {
+                if let Some((id, _)) = self.frontier.pop() { id }
+                else {
+                    self.finished = true;
+                    return StepResult::NoPath { step: self.prev_step }
+                }
+            }
+        };
+
+        self.current = Some(next);
+        let my_cost = match *self.world.cell(next).unwrap() {
+            Cell::Visited { g, h:_, k:_, parent:_ } => g,
+            _ => 0.0,
+        };
+        if next == start {
+            self.pruned.clear();
+            self.finished = true;
+            return StepResult::Found { step: self.prev_step, cost: my_cost }
+        }
+
+        let (x, y) = self.coords(next);
+        let mut opened = 0;
+        let start_coord = self.world.coords_for(start).unwrap();
+
+        // anything not explored from here was pruned
+        let dirs = self.successor_dirs(next);
+        self.pruned = ALL_DIRS.iter()
+            .filter(|&&dir| !dirs.contains(&dir) && !self.blocked(x + dir.0, y + dir.1))
+            .map(|&(dx, dy)| self.world.id_at((x + dx) as usize, (y + dy) as usize).unwrap())
+            .collect();
+
+        for dir in dirs {
+            let (jx, jy) = match self.jump(x, y, dir) {
+                Some(point) => point,
+                None => continue,
+            };
+            let id = self.world.id_at(jx as usize, jy as usize).unwrap();
+            let steps = (jx - x).abs().max((jy - y).abs()) as f32;
+            let step_cost = self.config.edge_cost(
+                &self.world,
+                (x as usize, y as usize),
+                ((x + dir.0) as usize, (y + dir.1) as usize));
+            let new_cost = my_cost + steps * step_cost;
+            let new_heur = self.config.estimate((jx as usize, jy as usize), start_coord);
+            let improved = match *self.world.cell(id).unwrap() {
+                Cell::Visited { g, h:_, k:_, parent:_ } => g > new_cost,
+                Cell::Open => true,
+                Cell::Obstacle => false,
+            };
+            if improved {
+                *self.world.cell_mut(id).unwrap() = Cell::Visited {
+                    g: new_cost,
+                    h: new_heur,
+                    k: 0.0,
+                    parent: next,
+                };
+                let cost = new_cost + new_heur;
+                self.frontier.push(id, cost);
+                opened += 1;
+            }
+        }
+
+        self.prev_step += 1;
+        StepResult::Expanded { step: self.prev_step, cell: next, opened }
+    }
+
+    fn world_view(&self) -> &World {
+        &self.world
+    }
+
+    fn config_view(&self) -> &AStarCfg {
+        &self.config
+    }
+
+    fn frontier_view(&self) -> Frontier {
+        self.frontier.sorted()
+    }
+
+    /// Jump points are joined by straight runs of cells which are filled in
+    fn path(&self) -> Option<Vec<Id>> {
+        let start = self.config.start.unwrap();
+        let goal = self.config.goal.unwrap();
+        if self.current != Some(start) {
+            return None
+        }
+        let mut path = vec![start];
+        let mut id = start;
+        while id != goal {
+            let parent = match self.world.cell(id) {
+                Some(Cell::Visited { g:_, h:_, k:_, parent }) => *parent,
+                _ => panic!("State corruption"),
+            };
+            let (mut x, mut y) = self.coords(id);
+            let (px, py) = self.coords(parent);
+            let (dx, dy) = ((px - x).signum(), (py - y).signum());
+            while (x, y) != (px, py) {
+                x += dx;
+                y += dy;
+                path.push(self.world.id_at(x as usize, y as usize).unwrap());
+            }
+            id = parent;
+        }
+        Some(path)
+    }
+
+    fn highlights(&self) -> Vec<(Id, Highlight)> {
+        let mut highlights: Vec<(Id, Highlight)> = self.pruned.iter()
+            .map(|&id| (id, Highlight::Pruned))
+            .collect();
+        for id in 0..self.world.width() * self.world.height() {
+            if let Some(Cell::Visited { .. }) = self.world.cell(id) {
+                highlights.push((id, Highlight::JumpPoint));
+            }
+        }
+        highlights
+    }
+
+    fn stats(&self) -> Vec<String> {
+        vec![format!("Expansions: {}", self.prev_step)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn mkcfg(w: &World, start: (usize, usize), goal: (usize, usize)) -> AStarCfg {
+        AStarCfg::new()
+            .with_goal(w.id_at(goal.0, goal.1).unwrap())
+            .with_start(w.id_at(start.0, start.1).unwrap())
+            .with_hueristic(Some(Heuristic::Euclidean))
+            .with_neighbors(Neighbors::CardinalAndDiagonal)
+    }
+
+    fn run(planner: &mut dyn Planner) -> (usize, f32) {
+        let expansions = run_search(planner);
+        // jump points are filled in, so the path moves one cell at a time
+        let world = planner.world_view();
+        for w in planner.path().unwrap().windows(2) {
+            let (ax, ay) = world.coords_for(w[0]).unwrap();
+            let (bx, by) = world.coords_for(w[1]).unwrap();
+            assert!(ax.max(bx) - ax.min(bx) <= 1 && ay.max(by) - ay.min(by) <= 1);
+        }
+        (expansions, path_cost(planner))
+    }
+
+    #[test]
+    fn requires_diagonal_moves() {
+        let w = World::new(4, 4, vec![Cell::Open; 16]).unwrap();
+        let cfg = mkcfg(&w, (0,0), (3,3)).with_neighbors(Neighbors::Cardinal);
+        assert!(JumpPointSearch::from_cfg(cfg, w).is_err());
+    }
+
+    #[test]
+    fn matches_astar() {
+        let mut cells = vec![Cell::Open; 100];
+        for &(x, y) in [(4,0), (4,1), (4,2), (4,3), (4,4), (4,5),
+                        (6,4), (6,5), (6,6), (6,7), (6,8), (6,9),
+                        (1,7), (2,7), (3,7), (8,2), (9,2)].iter() {
+            cells[y * 10 + x] = Cell::Obstacle;
+        }
+        let w = World::new(10, 10, cells).unwrap();
+        for &(start, goal) in [((0,0), (9,9)), ((9,0), (0,9)), ((2,8), (9,0))].iter() {
+            let cfg = mkcfg(&w, start, goal);
+            let mut jps = JumpPointSearch::from_cfg(cfg.clone(), w.clone()).unwrap();
+            let mut astar = AStar::from_cfg(cfg, w.clone()).unwrap();
+            let (_, jps_cost) = run(&mut jps);
+            let (_, astar_cost) = run(&mut astar);
+            assert!((jps_cost - astar_cost).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn fewer_expansions_on_open_map() {
+        let w = World::new(12, 12, vec![Cell::Open; 144]).unwrap();
+        let cfg = mkcfg(&w, (0,3), (11,7)).with_hueristic(None);
+        let mut jps = JumpPointSearch::from_cfg(cfg.clone(), w.clone()).unwrap();
+        let mut astar = AStar::from_cfg(cfg, w).unwrap();
+        let (jps_expanded, jps_cost) = run(&mut jps);
+        let (astar_expanded, astar_cost) = run(&mut astar);
+        assert!((jps_cost - astar_cost).abs() < 1e-4);
+        assert!(jps_expanded < astar_expanded);
+    }
+}
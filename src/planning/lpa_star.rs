@@ -0,0 +1,106 @@
+//! Lifelong Planning A*, an incremental search from the start toward the goal
+//! that keeps its g and rhs values while obstacles are edited.
+//!
+//! This is the same machinery as `DStarLite` with the search direction
+//! flipped, so g is the cost from the start and h estimates the cost to the
+//! goal. After an edit only the locally inconsistent cells are expanded again.
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::dstar_lite::DStarLite;
+use super::planner::*;
+
+#[derive(Clone)]
+pub struct LpaStar {
+    config: AStarCfg,
+    search: DStarLite,
+}
+
+impl LpaStar {
+
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<LpaStar, PlanningError> {
+
+        cfg.valid_for(&world)?;
+
+        // run the backward search rooted at the start
+        let reversed = AStarCfg {
+            goal: cfg.start,
+            start: cfg.goal,
+            ..cfg.clone()
+        };
+        Ok(LpaStar {
+            config: cfg,
+            search: DStarLite::from_cfg(reversed, world)?,
+        })
+    }
+}
+
+impl Planner for LpaStar {
+
+    fn box_clone(&self) -> Box<dyn Planner> { Box::new(self.clone()) }
+
+    fn algorithm(&self) -> Algorithm { Algorithm::LpaStar }
+
+    fn step(&mut self) -> StepResult { self.search.step() }
+
+    fn current(&self) -> Option<Id> { self.search.current() }
+
+    fn frontier_view(&self) -> Frontier { self.search.frontier_view() }
+
+    fn path(&self) -> Option<Vec<Id>> {
+        self.search.path().map(|mut path| { path.reverse(); path })
+    }
+
+    fn world_view(&self) -> &World { self.search.world_view() }
+
+    fn config_view(&self) -> &AStarCfg { &self.config }
+
+    fn highlights(&self) -> Vec<(Id, Highlight)> {
+        self.search.inconsistent().into_iter()
+            .map(|id| (id, Highlight::Inconsistent))
+            .collect()
+    }
+
+    fn stats(&self) -> Vec<String> { self.search.stats() }
+
+    fn supports_replanning(&self) -> bool { true }
+
+    fn toggle_obstacle(&mut self, id: Id) -> bool { self.search.toggle_obstacle(id) }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn edit_needs_fewer_expansions() {
+        let w = World::new(8, 8, vec![Cell::Open; 64]).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(7,0).unwrap())
+                    .with_start(w.id_at(0,7).unwrap())
+                    .with_hueristic(Some(Heuristic::Manhattan))
+                    .with_neighbors(Neighbors::Cardinal);
+        let mut lpa = LpaStar::from_cfg(cfg, w.clone()).unwrap();
+        let mut first = 0;
+        while lpa.step().expanded() { first += 1 }
+        assert_eq!(lpa.world_view().cell(lpa.start()),
+                   Some(&Cell::Visited { g: 0.0, h: 14.0, k: 14.0, parent: lpa.start() }));
+
+        // a wall away from the start only disturbs the cells behind it
+        for y in 0..3 {
+            assert!(lpa.toggle_obstacle(w.id_at(6,y).unwrap()));
+        }
+        assert!(!lpa.highlights().is_empty());
+        let mut repair = 0;
+        while lpa.step().expanded() { repair += 1 }
+        assert!(repair < first);
+        assert!(lpa.highlights().is_empty());
+
+        let path = lpa.path().unwrap();
+        assert_eq!(*path.first().unwrap(), lpa.start());
+        assert_eq!(*path.last().unwrap(), lpa.goal());
+        assert_eq!(path.len(), 15);
+    }
+}
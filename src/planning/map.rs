@@ -0,0 +1,277 @@
+//! Plain text maps, one character per cell and one line per row.
+//!
+//! `.` is open road, `,` grass, `~` mud and `#` an obstacle. `S` and `G` mark
+//! the start and goal on open road, and `*` the road cells of a path between
+//! them. Blank lines are ignored and every row must be the same width. When
+//! rendering search results `o` marks cells the search reached, which only
+//! goes one way and doesn't parse back.
+//!
+//! A `Map` round trips its start, goal and path. `World` parses from and
+//! prints as the same text but holds only the cells, so it rejects `S`, `G`
+//! and `*` rather than drop them.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::world::*;
+use super::error::PlanningError;
+
+/// A world along with the start, goal and a path marked on it
+#[derive(Clone, Debug)]
+pub struct Map {
+    pub world: World,
+    pub start: Option<Id>,
+    pub goal: Option<Id>,
+    /// Drawn as `*` when printing. Parsed from start to goal when any `*` is
+    /// marked, otherwise empty
+    pub path: Vec<Id>,
+}
+
+impl FromStr for Map {
+    type Err = PlanningError;
+
+    fn from_str(s: &str) -> Result<Map, PlanningError> {
+        let (mut map, marked) = parse_cells(s)?;
+        map.path = trace_path(&map, &marked)?;
+        Ok(map)
+    }
+}
+
+/// Parse map text into a map with no path and the cells marked `*`
+pub(crate) fn parse_cells(s: &str) -> Result<(Map, Vec<Id>), PlanningError> {
+        let rows: Vec<(usize, &str)> = s.lines()
+            .map(|line| line.trim())
+            .enumerate()
+            .filter(|&(_, line)| !line.is_empty())
+            .collect();
+        let width = match rows.first() {
+            Some((_, line)) => line.chars().count(),
+            None => return Err(PlanningError::EmptyMap),
+        };
+
+        let mut cells = Vec::with_capacity(width * rows.len());
+        let mut terrain = Vec::with_capacity(width * rows.len());
+        let (mut start, mut goal) = (None, None);
+        let mut marked = Vec::new();
+        for &(line, row) in rows.iter() {
+            let found = row.chars().count();
+            if found != width {
+                return Err(PlanningError::RaggedMap { line: line + 1, expected: width, found });
+            }
+            for (column, symbol) in row.chars().enumerate() {
+                let marker = match symbol {
+                    'S' => Some(&mut start),
+                    'G' => Some(&mut goal),
+                    _ => None,
+                };
+                if let Some(marker) = marker {
+                    if marker.is_some() {
+                        return Err(PlanningError::DuplicateMarker(symbol));
+                    }
+                    *marker = Some(cells.len());
+                }
+                if symbol == '*' {
+                    marked.push(cells.len());
+                }
+                let (cell, ground) = match symbol {
+                    '.' | 'S' | 'G' | '*' => (Cell::Open, Terrain::Road),
+                    ','                   => (Cell::Open, Terrain::Grass),
+                    '~'                   => (Cell::Open, Terrain::Mud),
+                    '#'                   => (Cell::Obstacle, Terrain::Road),
+                    _ => return Err(PlanningError::UnknownMapSymbol { line: line + 1, column: column + 1, symbol }),
+                };
+                cells.push(cell);
+                terrain.push(ground);
+            }
+        }
+
+        let mut world = World::new(width, rows.len(), cells)?;
+        for (id, ground) in terrain.into_iter().enumerate() {
+            world.set_terrain(id, ground);
+        }
+        Ok((Map { world, start, goal, path: Vec::new() }, marked))
+}
+
+/// Order the marked cells into a path from start to goal. Each step goes to
+/// the one marked cell beside the last, checking straight moves before
+/// diagonal ones so a path that turns a corner isn't mistaken for a fork.
+fn trace_path(map: &Map, marked: &[Id]) -> Result<Vec<Id>, PlanningError> {
+    if marked.is_empty() {
+        return Ok(Vec::new())
+    }
+    let (start, goal) = match (map.start, map.goal) {
+        (Some(start), Some(goal)) => (start, goal),
+        _ => return Err(PlanningError::BrokenPath),
+    };
+    let world = &map.world;
+    let mut unused = vec![false; world.width() * world.height()];
+    for &id in marked {
+        unused[id] = true;
+    }
+    let beside = |id: Id, strat: Neighbors| -> Vec<Id> {
+        world.iter_neighbor_ids(id, strat).unwrap()
+            .filter_map(|(x,y)| world.id_at(x,y))
+            .collect()
+    };
+
+    let mut path = vec![start];
+    let mut here = start;
+    loop {
+        let mut next: Vec<Id> = beside(here, Neighbors::Cardinal).into_iter()
+            .filter(|&id| unused[id])
+            .collect();
+        if next.is_empty() {
+            next = beside(here, Neighbors::CardinalAndDiagonal).into_iter()
+                .filter(|&id| unused[id])
+                .collect();
+        }
+        match next.as_slice() {
+            [] => break,
+            [id] => {
+                unused[*id] = false;
+                path.push(*id);
+                here = *id;
+            }
+            _ => return Err(PlanningError::BrokenPath),
+        }
+    }
+    if path.len() <= marked.len() || !beside(here, Neighbors::CardinalAndDiagonal).contains(&goal) {
+        return Err(PlanningError::BrokenPath)
+    }
+    path.push(goal);
+    Ok(path)
+}
+
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&render(&self.world, self.start, self.goal, &self.path))
+    }
+}
+
+/// Draw a world as map text with the search overlaid on it
+pub fn render(world: &World, start: Option<Id>, goal: Option<Id>, path: &[Id]) -> String {
+    let mut text = String::with_capacity((world.width() + 1) * world.height());
+    for y in 0..world.height() {
+        for x in 0..world.width() {
+            let id = world.id_at(x, y).unwrap();
+            text.push(match world.cell(id).unwrap() {
+                _ if Some(id) == start => 'S',
+                _ if Some(id) == goal => 'G',
+                _ if path.contains(&id) => '*',
+                Cell::Obstacle => '#',
+                Cell::Visited { .. } => 'o',
+                Cell::Open => match world.terrain(id).unwrap() {
+                    Terrain::Road => '.',
+                    Terrain::Grass => ',',
+                    Terrain::Mud => '~',
+                },
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use planning::astar::*;
+    use planning::planner::Planner;
+
+    #[test]
+    fn parses_markers_and_terrain() {
+        let map: Map = "
+            S.#.
+            ,~#G
+        ".parse().unwrap();
+        assert_eq!((map.world.width(), map.world.height()), (4, 2));
+        assert_eq!(map.start, map.world.id_at(0,0));
+        assert_eq!(map.goal, map.world.id_at(3,1));
+        assert_eq!(map.world.cell_at(2,1), Some(&Cell::Obstacle));
+        assert_eq!(map.world.terrain(map.world.id_at(0,1).unwrap()), Some(Terrain::Grass));
+        assert_eq!(map.world.terrain(map.world.id_at(1,1).unwrap()), Some(Terrain::Mud));
+    }
+
+    #[test]
+    fn loads_example_map() {
+        let map: Map = include_str!("../../maps/detour.txt").parse().unwrap();
+        assert_eq!((map.world.width(), map.world.height()), (10, 10));
+        assert!(map.start.is_some() && map.goal.is_some());
+        assert!(!map.world.uniform_terrain());
+    }
+
+    #[test]
+    fn renders_round_trip() {
+        let text = "S.#.\n,~#G\n";
+        let map: Map = text.parse().unwrap();
+        assert_eq!(render(&map.world, map.start, map.goal, &[]), text);
+
+        let mut world = map.world.clone();
+        *world.cell_at_mut(1,0).unwrap() = Cell::Visited { g: 1.0, h: 0.0, k: 0.0, parent: 0 };
+        let path = [world.id_at(0,1).unwrap()];
+        assert_eq!(render(&world, map.start, map.goal, &path), "So#.\n*~#G\n");
+    }
+
+    #[test]
+    fn world_text() {
+        let world: World = ".,#\n~..\n".parse().unwrap();
+        assert_eq!(world.to_string(), ".,#\n~..\n");
+        assert_eq!("S.#\n~.G\n".parse::<World>().err(), Some(PlanningError::MarkerInWorld('S')));
+        assert_eq!("..#\n~.G\n".parse::<World>().err(), Some(PlanningError::MarkerInWorld('G')));
+        assert_eq!("S.\n.".parse::<World>().err(),
+                   Some(PlanningError::RaggedMap { line: 2, expected: 2, found: 1 }));
+
+        assert_eq!("..#\n~*.\n".parse::<World>().err(), Some(PlanningError::MarkerInWorld('*')));
+
+        let mut map: Map = "S.#\n~.G\n".parse().unwrap();
+        assert_eq!(map.to_string(), "S.#\n~.G\n");
+        map.path = vec![1, 4];
+        assert_eq!(map.to_string(), "S*#\n~*G\n");
+    }
+
+    #[test]
+    fn path_round_trip() {
+        let text = "
+            S..#....
+            ##.#.##.
+            ...#..#G
+            .#...#..
+        ";
+        let mut map: Map = text.parse().unwrap();
+        assert!(map.path.is_empty());
+        let cfg = AStarCfg::new()
+            .with_start(map.start.unwrap())
+            .with_goal(map.goal.unwrap())
+            .with_neighbors(Neighbors::CardinalAndDiagonal);
+        let mut astar = AStar::from_cfg(cfg, map.world.clone()).unwrap();
+        while astar.step().expanded() {}
+        map.path = astar.path().unwrap();
+        assert_eq!(map.to_string(), "S*.#....\n##*#.##.\n..*#**#G\n.#.*.#*.\n");
+
+        let parsed: Map = map.to_string().parse().unwrap();
+        assert_eq!(parsed.start, map.start);
+        assert_eq!(parsed.goal, map.goal);
+        assert_eq!(parsed.path, map.path);
+        assert_eq!(parsed.to_string(), map.to_string());
+
+        // cardinal paths turn corners next to their own earlier cells
+        let parsed: Map = "S*.\n.*G\n".parse().unwrap();
+        assert_eq!(parsed.path, vec![0, 1, 4, 5]);
+    }
+
+    #[test]
+    fn rejects_bad_maps() {
+        assert_eq!("\n\n".parse::<Map>().err(), Some(PlanningError::EmptyMap));
+        assert_eq!("..\n...".parse::<Map>().err(),
+                   Some(PlanningError::RaggedMap { line: 2, expected: 2, found: 3 }));
+        assert_eq!("S.\n.S".parse::<Map>().err(), Some(PlanningError::DuplicateMarker('S')));
+        assert_eq!("..\n.x".parse::<Map>().err(),
+                   Some(PlanningError::UnknownMapSymbol { line: 2, column: 2, symbol: 'x' }));
+        // paths missing an end, with a gap, a fork or a stray cell
+        assert_eq!("S*.\n...".parse::<Map>().err(), Some(PlanningError::BrokenPath));
+        assert_eq!("S.*G".parse::<Map>().err(), Some(PlanningError::BrokenPath));
+        assert_eq!("*S*\n.G.".parse::<Map>().err(), Some(PlanningError::BrokenPath));
+        assert_eq!("S*G\n...\n..*".parse::<Map>().err(), Some(PlanningError::BrokenPath));
+    }
+}
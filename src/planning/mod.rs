@@ -1,2 +1,17 @@
+pub mod analysis;
+pub mod ara_star;
 pub mod astar;
+pub mod bfs;
+pub mod bidirectional;
+pub mod dijkstra;
+pub mod dstar_lite;
+pub mod error;
+pub mod frontier;
+pub mod greedy;
+pub mod ida_star;
+pub mod jps;
+pub mod lpa_star;
+pub mod map;
+pub mod planner;
+pub mod theta_star;
 pub mod world;
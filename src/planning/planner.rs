@@ -0,0 +1,481 @@
+use std::fmt;
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::ara_star::AraStar;
+use super::bfs::BreadthFirst;
+use super::bidirectional::BidirectionalAStar;
+use super::dijkstra::Dijkstra;
+use super::dstar_lite::DStarLite;
+use super::greedy::GreedyBestFirst;
+use super::ida_star::IdaStar;
+use super::jps::JumpPointSearch;
+use super::lpa_star::LpaStar;
+use super::theta_star::ThetaStar;
+
+/// Frontier entries as (cell Id, priority), best candidate first
+pub type Frontier = Vec<(Id, f32)>;
+
+/// Algorithm specific cell markings for the view to draw
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Highlight {
+    /// Cell was changed while repairing the search after a map edit
+    Updated,
+    /// Cell has different g and rhs values and is waiting to be expanded
+    Inconsistent,
+    /// Cell was added to the frontier by jump point search
+    JumpPoint,
+    /// Neighbor of the current cell that jump point search skipped
+    Pruned,
+    /// Cell reached by the search growing from the start
+    ForwardVisited,
+    /// Cell reached by the search growing from the goal
+    BackwardVisited,
+    /// Cell where the two halves of a bidirectional search join
+    Meeting,
+    /// Cell on the current branch of a depth-first search
+    OnStack,
+    /// Cell whose heuristic is more than its true cost to go
+    Overestimate,
+    /// Cell whose heuristic drops by more than the cost of a move
+    TriangleBreak,
+}
+
+/// What happened during one call to `Planner::step`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepResult {
+    /// A cell was expanded and the search goes on
+    Expanded {
+        /// steps taken so far
+        step: usize,
+        /// the cell that was worked on
+        cell: Id,
+        /// cells added to the frontier or given a better cost
+        opened: usize,
+    },
+    /// The search reached its target and `path` is ready
+    Found { step: usize, cost: f32 },
+    /// Every reachable cell was searched without reaching the target
+    NoPath { step: usize },
+    /// The search had already ended so nothing was done
+    AlreadyFinished,
+}
+
+impl StepResult {
+
+    /// True if the search can carry on after this step
+    pub fn expanded(&self) -> bool {
+        matches!(self, StepResult::Expanded { .. })
+    }
+
+    /// Steps taken so far, unless the search had already ended
+    pub fn step(&self) -> Option<usize> {
+        match *self {
+            StepResult::Expanded { step, .. } |
+            StepResult::Found { step, .. } |
+            StepResult::NoPath { step } => Some(step),
+            StepResult::AlreadyFinished => None,
+        }
+    }
+}
+
+/// One thing a search did to a single cell during a step
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepEvent {
+    /// Cell taken off the frontier to be expanded, with its priority
+    Popped { cell: Id, f: f32 },
+    /// Cell reached for the first time and added to the frontier
+    Opened { cell: Id, g: f32, h: f32 },
+    /// Cell already reached that was given a cheaper cost
+    Improved { cell: Id, old_g: f32, new_g: f32 },
+    /// Neighbor skipped because it is an obstacle
+    Blocked { cell: Id },
+}
+
+impl StepEvent {
+
+    /// Describe the event using the coordinates of its cell
+    pub fn describe(&self, world: &World) -> String {
+        let coords = |id: Id| world.coords_for(id).unwrap();
+        match *self {
+            StepEvent::Popped { cell, f } =>
+                format!("pop {:?} f: {:0.1}", coords(cell), f),
+            StepEvent::Opened { cell, g, h } =>
+                format!("  open {:?} g: {:0.1} h: {:0.1}", coords(cell), g, h),
+            StepEvent::Improved { cell, old_g, new_g } =>
+                format!("  improve {:?} g: {:0.1} -> {:0.1}", coords(cell), old_g, new_g),
+            StepEvent::Blocked { cell } =>
+                format!("  skip {:?} obstacle", coords(cell)),
+        }
+    }
+}
+
+/// Common interface for the step-by-step search algorithms
+pub trait Planner {
+
+    /// Copy of the planner and its search state, used to keep a history
+    fn box_clone(&self) -> Box<dyn Planner>;
+
+    /// Which algorithm is doing the planning
+    fn algorithm(&self) -> Algorithm;
+
+    /// Do one step of the search and report what happened
+    fn step(&mut self) -> StepResult;
+
+    /// The most recently expanded cell
+    fn current(&self) -> Option<Id>;
+
+    /// What the last call to `step` did, cell by cell, for planners that
+    /// keep a log
+    fn events(&self) -> &[StepEvent] {
+        &[]
+    }
+
+    /// Cells waiting to be expanded
+    fn frontier_view(&self) -> Frontier;
+
+    /// Heading for the frontier list in the sidebar
+    fn frontier_title(&self) -> &'static str {
+        "Frontier:"
+    }
+
+    /// Cells from start to goal, available once the search has finished
+    fn path(&self) -> Option<Vec<Id>>;
+
+    fn world_view(&self) -> &World;
+
+    fn config_view(&self) -> &AStarCfg;
+
+    fn start(&self) -> Id {
+        self.config_view().start.unwrap()
+    }
+
+    fn goal(&self) -> Id {
+        self.config_view().goal.unwrap()
+    }
+
+    /// Extra cells to mark in the view
+    fn highlights(&self) -> Vec<(Id, Highlight)> {
+        Vec::new()
+    }
+
+    /// Extra lines of algorithm specific information for the sidebar
+    fn stats(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Number of paths kept by anytime planners
+    fn solution_count(&self) -> usize {
+        0
+    }
+
+    /// Which of the kept paths `path` returns
+    fn selected_solution(&self) -> Option<usize> {
+        None
+    }
+
+    /// Choose which of the kept paths `path` returns
+    fn select_solution(&mut self, _idx: usize) {}
+
+    /// Whether obstacles may be edited while the search is active
+    fn supports_replanning(&self) -> bool {
+        false
+    }
+
+    /// Toggle an obstacle mid-search, returning false if it was not allowed
+    fn toggle_obstacle(&mut self, _id: Id) -> bool {
+        false
+    }
+}
+
+impl Clone for Box<dyn Planner> {
+    fn clone(&self) -> Box<dyn Planner> {
+        self.box_clone()
+    }
+}
+
+/// The available planners
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    AStar,
+    Dijkstra,
+    BreadthFirst,
+    GreedyBestFirst,
+    DStarLite,
+    LpaStar,
+    JumpPoint,
+    ThetaStar,
+    LazyThetaStar,
+    Bidirectional,
+    AraStar,
+    IdaStar,
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Algorithm::AStar           => write!(f, "A*"),
+            Algorithm::Dijkstra        => write!(f, "Dijkstra"),
+            Algorithm::BreadthFirst    => write!(f, "Breadth First"),
+            Algorithm::GreedyBestFirst => write!(f, "Greedy Best First"),
+            Algorithm::DStarLite       => write!(f, "D* Lite"),
+            Algorithm::LpaStar         => write!(f, "LPA*"),
+            Algorithm::JumpPoint       => write!(f, "Jump Point"),
+            Algorithm::ThetaStar       => write!(f, "Theta*"),
+            Algorithm::LazyThetaStar   => write!(f, "Lazy Theta*"),
+            Algorithm::Bidirectional   => write!(f, "Bidirectional A*"),
+            Algorithm::AraStar         => write!(f, "ARA*"),
+            Algorithm::IdaStar         => write!(f, "IDA*"),
+        }
+    }
+}
+
+impl Algorithm {
+
+    /// Cycle through the algorithms, used by the GUI
+    pub fn next(self) -> Algorithm {
+        match self {
+            Algorithm::AStar           => Algorithm::Dijkstra,
+            Algorithm::Dijkstra        => Algorithm::BreadthFirst,
+            Algorithm::BreadthFirst    => Algorithm::GreedyBestFirst,
+            Algorithm::GreedyBestFirst => Algorithm::DStarLite,
+            Algorithm::DStarLite       => Algorithm::LpaStar,
+            Algorithm::LpaStar         => Algorithm::JumpPoint,
+            Algorithm::JumpPoint       => Algorithm::ThetaStar,
+            Algorithm::ThetaStar       => Algorithm::LazyThetaStar,
+            Algorithm::LazyThetaStar   => Algorithm::Bidirectional,
+            Algorithm::Bidirectional   => Algorithm::AraStar,
+            Algorithm::AraStar         => Algorithm::IdaStar,
+            Algorithm::IdaStar         => Algorithm::AStar,
+        }
+    }
+
+    /// Check the config and world are usable by this algorithm
+    pub fn valid_for(self, cfg: &AStarCfg, world: &World) -> Result<(), PlanningError> {
+        cfg.valid_for(world)?;
+        let reason = match (self, cfg.neighbors) {
+            (Algorithm::JumpPoint, Neighbors::Cardinal) => "requires diagonal moves",
+            (Algorithm::JumpPoint, _) if !world.uniform_terrain() => "requires uniform terrain",
+            (Algorithm::AraStar, _) if cfg.weight <= 1.0 => "needs a heuristic weight above 1",
+            _ if cfg.weight != 1.0 && !self.uses_weight() => "ignores the heuristic weight",
+            _ if cfg.direction == Direction::Forward && !self.allows_forward() =>
+                "only searches backward from the goal",
+            _ => return Ok(()),
+        };
+        Err(PlanningError::Unsupported { algorithm: self, reason })
+    }
+
+    /// True if the algorithm ranks cells with the weighted heuristic, the
+    /// others need a weight of 1
+    pub fn uses_weight(self) -> bool {
+        matches!(self, Algorithm::AStar | Algorithm::AraStar)
+    }
+
+    /// True if the algorithm can run with a forward search direction. The
+    /// planners built on `AStar` follow it, LPA* and bidirectional A* always
+    /// search forward anyway.
+    fn allows_forward(self) -> bool {
+        matches!(self,
+                 Algorithm::AStar | Algorithm::Dijkstra | Algorithm::BreadthFirst |
+                 Algorithm::GreedyBestFirst | Algorithm::LpaStar | Algorithm::Bidirectional)
+    }
+
+    /// Construct a planner running this algorithm, failing for configs it
+    /// can't follow
+    pub fn build(self, cfg: AStarCfg, world: World) -> Result<Box<dyn Planner>, PlanningError> {
+        self.valid_for(&cfg, &world)?;
+        Ok(match self {
+            Algorithm::AStar =>
+                Box::new(AStar::from_cfg(cfg, world)?),
+            Algorithm::Dijkstra =>
+                Box::new(Dijkstra::from_cfg(cfg, world)?),
+            Algorithm::BreadthFirst =>
+                Box::new(BreadthFirst::from_cfg(cfg, world)?),
+            Algorithm::GreedyBestFirst =>
+                Box::new(GreedyBestFirst::from_cfg(cfg, world)?),
+            Algorithm::DStarLite =>
+                Box::new(DStarLite::from_cfg(cfg, world)?),
+            Algorithm::LpaStar =>
+                Box::new(LpaStar::from_cfg(cfg, world)?),
+            Algorithm::JumpPoint =>
+                Box::new(JumpPointSearch::from_cfg(cfg, world)?),
+            Algorithm::ThetaStar =>
+                Box::new(ThetaStar::from_cfg(cfg, world)?),
+            Algorithm::LazyThetaStar =>
+                Box::new(ThetaStar::lazy_from_cfg(cfg, world)?),
+            Algorithm::Bidirectional =>
+                Box::new(BidirectionalAStar::from_cfg(cfg, world)?),
+            Algorithm::AraStar =>
+                Box::new(AraStar::from_cfg(cfg, world)?),
+            Algorithm::IdaStar =>
+                Box::new(IdaStar::from_cfg(cfg, world)?),
+        })
+    }
+}
+
+/// Step a planner until it stops expanding cells, returning the expansions
+#[cfg(test)]
+pub(crate) fn run_search(planner: &mut dyn Planner) -> usize {
+    let mut expansions = 0;
+    while planner.step().expanded() { expansions += 1 }
+    expansions
+}
+
+/// Cost of the path a finished planner found, summed move by move
+#[cfg(test)]
+pub(crate) fn path_cost(planner: &dyn Planner) -> f32 {
+    let world = planner.world_view();
+    planner.path().unwrap().windows(2)
+        .map(|w| planner.config_view().edge_cost(world,
+                                                 world.coords_for(w[0]).unwrap(),
+                                                 world.coords_for(w[1]).unwrap()))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn build(algorithm: Algorithm, neighbors: Neighbors, walls: &[(usize, usize)]) -> Box<dyn Planner> {
+        let mut cells = vec![Cell::Open; 36];
+        for &(x, y) in walls {
+            cells[y * 6 + x] = Cell::Obstacle;
+        }
+        let w = World::new(6, 6, cells).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,0).unwrap())
+                    .with_start(w.id_at(5,0).unwrap())
+                    .with_hueristic(Some(Heuristic::Euclidean))
+                    .with_neighbors(neighbors)
+                    .with_weight(if algorithm == Algorithm::AraStar { 2.0 } else { 1.0 });
+        algorithm.build(cfg, w).unwrap()
+    }
+
+    fn run(algorithm: Algorithm, neighbors: Neighbors) -> Box<dyn Planner> {
+        let wall: Vec<_> = (0..5).map(|y| (3, y)).collect();
+        let mut planner = build(algorithm, neighbors, &wall);
+        run_search(&mut *planner);
+        planner
+    }
+
+    #[test]
+    fn optimal_planners_agree() {
+        for &n in [Neighbors::Cardinal, Neighbors::CardinalAndDiagonal].iter() {
+            let astar = run(Algorithm::AStar, n);
+            for &alg in [Algorithm::Dijkstra, Algorithm::DStarLite,
+                         Algorithm::LpaStar, Algorithm::Bidirectional].iter() {
+                let other = run(alg, n);
+                assert!((path_cost(&*astar) - path_cost(&*other)).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn optimal_planners_avoid_mud() {
+        let mut w = World::new(7, 5, vec![Cell::Open; 35]).unwrap();
+        for y in 1..4 {
+            for x in 2..5 {
+                let id = w.id_at(x, y).unwrap();
+                w.set_terrain(id, Terrain::Mud);
+            }
+        }
+        for &n in [Neighbors::Cardinal, Neighbors::CardinalAndDiagonal].iter() {
+            let cfg = AStarCfg::new()
+                        .with_goal(w.id_at(0,2).unwrap())
+                        .with_start(w.id_at(6,2).unwrap())
+                        .with_hueristic(Some(Heuristic::Euclidean))
+                        .with_neighbors(n);
+            let mut costs = Vec::new();
+            for &alg in [Algorithm::AStar, Algorithm::Dijkstra, Algorithm::DStarLite,
+                         Algorithm::LpaStar, Algorithm::Bidirectional].iter() {
+                let mut planner = alg.build(cfg.clone(), w.clone()).unwrap();
+                while planner.step().expanded() {}
+                for &id in planner.path().unwrap().iter() {
+                    assert_eq!(w.terrain(id), Some(Terrain::Road));
+                }
+                costs.push(path_cost(&*planner));
+            }
+            assert!(costs.iter().all(|c| (c - costs[0]).abs() < 1e-4));
+        }
+        let cfg = AStarCfg::new()
+                    .with_goal(0)
+                    .with_start(6)
+                    .with_neighbors(Neighbors::CardinalAndDiagonal);
+        assert!(Algorithm::JumpPoint.valid_for(&cfg, &w).is_err());
+    }
+
+    #[test]
+    fn all_planners_find_path() {
+        let mut alg = Algorithm::AStar;
+        loop {
+            let planner = run(alg, Neighbors::CardinalAndDiagonal);
+            let path = planner.path().unwrap();
+            assert_eq!(*path.first().unwrap(), planner.start());
+            assert_eq!(*path.last().unwrap(), planner.goal());
+            assert_eq!(planner.algorithm(), alg);
+            alg = alg.next();
+            if alg == Algorithm::AStar { break }
+        }
+    }
+
+    #[test]
+    fn clones_keep_search_state() {
+        let wall: Vec<_> = (0..5).map(|y| (3, y)).collect();
+        let mut alg = Algorithm::AStar;
+        loop {
+            let mut planner = build(alg, Neighbors::CardinalAndDiagonal, &wall);
+            for _ in 0..3 { planner.step(); }
+            let before = planner.current();
+            let snapshot = planner.clone();
+            let next = planner.step();
+            // stepping the original leaves the copy alone
+            assert_eq!(snapshot.current(), before);
+            // and the copy carries on exactly where the original was
+            let mut replay = snapshot.clone();
+            assert_eq!(replay.step(), next);
+            assert_eq!(replay.current(), planner.current());
+            alg = alg.next();
+            if alg == Algorithm::AStar { break }
+        }
+    }
+
+    #[test]
+    fn all_planners_report_outcome() {
+        let mut alg = Algorithm::AStar;
+        loop {
+            let wall: Vec<_> = (0..5).map(|y| (3, y)).collect();
+            let mut planner = build(alg, Neighbors::CardinalAndDiagonal, &wall);
+            let mut last = 0;
+            let result = loop {
+                match planner.step() {
+                    StepResult::Expanded { step, .. } => {
+                        assert_eq!(step, last + 1);
+                        last = step;
+                    }
+                    result => break result,
+                }
+            };
+            match result {
+                StepResult::Found { step, cost } => {
+                    assert_eq!(step, last);
+                    assert!((cost - path_cost(&*planner)).abs() < 1e-4, "{}", alg);
+                }
+                other => panic!("{} ended with {:?}", alg, other),
+            }
+            assert_eq!(planner.step(), StepResult::AlreadyFinished);
+
+            // boxing in the goal cuts it off from the start
+            let mut planner = build(alg, Neighbors::CardinalAndDiagonal, &[(1, 0), (0, 1), (1, 1)]);
+            let mut result = planner.step();
+            while result.expanded() { result = planner.step() }
+            assert!(matches!(result, StepResult::NoPath { .. }), "{} ended with {:?}", alg, result);
+            assert!(planner.path().is_none());
+            assert_eq!(planner.step(), StepResult::AlreadyFinished);
+
+            alg = alg.next();
+            if alg == Algorithm::AStar { break }
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Theta* and Lazy Theta*, any-angle variants of A*.
+//!
+//! When a neighbor can see the parent of the cell being expanded it takes that
+//! parent directly, so paths are made of straight segments between corners
+//! instead of grid moves. Costs are the Euclidean length of those segments
+//! scaled by the average terrain cost along them.
+//!
+//! Lazy Theta* assumes line of sight when a cell is reached and only checks it
+//! once the cell is expanded, falling back to the best expanded neighbor. This
+//! trades a few worse parents for far fewer line of sight checks.
+//!
+//! Like `AStar` the search runs from the goal back toward the start.
+
+use super::world::*;
+use super::astar::*;
+use super::error::PlanningError;
+use super::planner::*;
+use super::frontier::IndexedHeap;
+
+#[derive(Clone)]
+pub struct ThetaStar {
+    config: AStarCfg,
+    current: Option<Id>,
+    frontier: IndexedHeap,
+    world: World,
+    prev_step: usize,
+    lazy: bool,
+    closed: Vec<bool>,
+    finished: bool,
+    sight_checks: usize,
+}
+
+impl ThetaStar {
+
+    pub fn from_cfg(cfg: AStarCfg, world: World) -> Result<ThetaStar, PlanningError> {
+        ThetaStar::with_laziness(cfg, world, false)
+    }
+
+    pub fn lazy_from_cfg(cfg: AStarCfg, world: World) -> Result<ThetaStar, PlanningError> {
+        ThetaStar::with_laziness(cfg, world, true)
+    }
+
+    fn with_laziness(cfg: AStarCfg, world: World, lazy: bool) -> Result<ThetaStar, PlanningError> {
+
+        let algorithm = if lazy { Algorithm::LazyThetaStar } else { Algorithm::ThetaStar };
+        algorithm.valid_for(&cfg, &world)?;
+
+        let n = world.width() * world.height();
+        Ok(ThetaStar {
+            config: cfg,
+            current: None,
+            frontier: IndexedHeap::new(n),
+            world,
+            prev_step: 0,
+            lazy,
+            closed: vec![false; n],
+            finished: false,
+            sight_checks: 0,
+        })
+    }
+
+    fn visited(&self, id: Id) -> Option<(f32, Id)> {
+        match self.world.cell(id) {
+            Some(Cell::Visited { g, h:_, k:_, parent }) => Some((*g, *parent)),
+            _ => None,
+        }
+    }
+
+    fn line_of_sight(&mut self, a: Id, b: Id) -> bool {
+        self.sight_checks += 1;
+        self.world.line_of_sight(a, b)
+    }
+
+    /// Length of the segment between two cells scaled by its terrain
+    fn distance(&self, a: Id, b: Id) -> f32 {
+        self.world.segment_cost(a, b).unwrap()
+    }
+
+    fn neighbors(&self, id: Id) -> Vec<Id> {
+        self.world.iter_neighbor_ids(id, self.config.neighbors).unwrap()
+            .filter_map(|(x,y)| self.world.id_at(x,y))
+            .filter(|&n| self.world.cell(n) != Some(&Cell::Obstacle))
+            .collect()
+    }
+
+    /// Lazy Theta* repair of a parent that turned out to be hidden
+    fn set_vertex(&mut self, id: Id) {
+        let (_, parent) = self.visited(id).unwrap();
+        if parent == id || self.line_of_sight(parent, id) {
+            return
+        }
+        let mut best: Option<(f32, Id)> = None;
+        for n in self.neighbors(id) {
+            if !self.closed[n] { continue }
+            if let Some((g, _)) = self.visited(n) {
+                let cost = g + self.distance(n, id);
+                match best {
+                    Some((b, _)) if b <= cost => {}
+                    _ => best = Some((cost, n)),
+                }
+            }
+        }
+        if let (Some((g, parent)), Some(Cell::Visited { g: old_g, h:_, k:_, parent: old_parent }))
+            = (best, self.world.cell_mut(id))
+        {
+            *old_g = g;
+            *old_parent = parent;
+        }
+    }
+}
+
+impl Planner for ThetaStar {
+
+    fn box_clone(&self) -> Box<dyn Planner> {
+        Box::new(self.clone())
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        if self.lazy { Algorithm::LazyThetaStar } else { Algorithm::ThetaStar }
+    }
+
+    fn current(&self) -> Option<Id> {
+        self.current
+    }
+
+    fn step(&mut self) -> StepResult {
+
+        let start = self.config.start.unwrap();
+        let goal = self.config.goal.unwrap();
+
+        // check if done
+        if self.finished {
+            return StepResult::AlreadyFinished
+        }
+
+        // get the next cell
+        let next: Id = match self.current {
+            // first time step is called, use goal
+            None => {
+                *self.world.cell_mut(goal).unwrap() =
+                    Cell::Visited { g: 0.0, h: 0.0, k: 0.0, parent: goal };
+                goal
+            }
+            Some(_) => {
+                if let Some((id, _)) = self.frontier.pop() { id }
+                else {
+                    self.finished = true;
+                    return StepResult::NoPath { step: self.prev_step }
+                }
+            }
+        };
+
+        if self.lazy {
+            self.set_vertex(next);
+        }
+        self.closed[next] = true;
+        self.current = Some(next);
+        let (my_cost, my_parent) = self.visited(next).unwrap();
+        if next == start {
+            self.finished = true;
+            return StepResult::Found { step: self.prev_step, cost: my_cost }
+        }
+
+        let start_coord = self.world.coords_for(start).unwrap();
+        let mut opened = 0;
+
+        for n in self.neighbors(next) {
+            if self.closed[n] { continue }
+            // try to skip straight to our parent before using a grid move
+            let (new_cost, parent) =
+                if self.lazy || self.line_of_sight(my_parent, n) {
+                    let (parent_cost, _) = self.visited(my_parent).unwrap();
+                    (parent_cost + self.distance(my_parent, n), my_parent)
+                } else {
+                    (my_cost + self.distance(next, n), next)
+                };
+            let improved = match self.visited(n) {
+                Some((g, _)) => g > new_cost,
+                None => true,
+            };
+            if improved {
+                let new_heur = self.config.estimate(self.world.coords_for(n).unwrap(), start_coord);
+                *self.world.cell_mut(n).unwrap() = Cell::Visited {
+                    g: new_cost,
+                    h: new_heur,
+                    k: 0.0,
+                    parent,
+                };
+                let cost = new_cost + new_heur;
+                self.frontier.push(n, cost);
+                opened += 1;
+            }
+        }
+
+        self.prev_step += 1;
+        StepResult::Expanded { step: self.prev_step, cell: next, opened }
+    }
+
+    fn world_view(&self) -> &World {
+        &self.world
+    }
+
+    fn config_view(&self) -> &AStarCfg {
+        &self.config
+    }
+
+    fn frontier_view(&self) -> Frontier {
+        self.frontier.sorted()
+    }
+
+    /// Only the corners of the path are returned, joined by straight lines
+    fn path(&self) -> Option<Vec<Id>> {
+        let start = self.config.start.unwrap();
+        let goal = self.config.goal.unwrap();
+        if self.current != Some(start) {
+            return None
+        }
+        let mut path = vec![start];
+        let mut id = start;
+        while id != goal {
+            id = match self.visited(id) {
+                Some((_, parent)) => parent,
+                None => panic!("State corruption"),
+            };
+            path.push(id);
+        }
+        Some(path)
+    }
+
+    fn stats(&self) -> Vec<String> {
+        vec![
+            format!("Expansions: {}", self.prev_step),
+            format!("Line of sight checks: {}", self.sight_checks),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn path_length(planner: &dyn Planner) -> f32 {
+        let world = planner.world_view();
+        planner.path().unwrap().windows(2)
+            .map(|w| {
+                assert!(world.line_of_sight(w[0], w[1]));
+                world.distance(w[0], w[1]).unwrap()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn straight_line_when_open() {
+        let w = World::new(8, 8, vec![Cell::Open; 64]).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,0).unwrap())
+                    .with_start(w.id_at(7,3).unwrap())
+                    .with_hueristic(Some(Heuristic::Euclidean))
+                    .with_neighbors(Neighbors::CardinalAndDiagonal);
+        let mut theta = ThetaStar::from_cfg(cfg.clone(), w.clone()).unwrap();
+        let mut lazy = ThetaStar::lazy_from_cfg(cfg, w).unwrap();
+        run_search(&mut theta);
+        run_search(&mut lazy);
+        assert_eq!(theta.path().unwrap().len(), 2);
+        assert_eq!(lazy.path().unwrap().len(), 2);
+        assert!((path_length(&theta) - 58.0f32.sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn shorter_than_grid_path() {
+        let mut cells = vec![Cell::Open; 100];
+        for y in 2..10 {
+            cells[y * 10 + 5] = Cell::Obstacle;
+        }
+        let w = World::new(10, 10, cells).unwrap();
+        let cfg = AStarCfg::new()
+                    .with_goal(w.id_at(0,9).unwrap())
+                    .with_start(w.id_at(9,8).unwrap())
+                    .with_hueristic(Some(Heuristic::Euclidean))
+                    .with_neighbors(Neighbors::CardinalAndDiagonal);
+        let mut theta = ThetaStar::from_cfg(cfg.clone(), w.clone()).unwrap();
+        let mut lazy = ThetaStar::lazy_from_cfg(cfg.clone(), w.clone()).unwrap();
+        let mut astar = AStar::from_cfg(cfg, w).unwrap();
+        run_search(&mut theta);
+        run_search(&mut lazy);
+        run_search(&mut astar);
+        let grid = path_length(&astar);
+        assert!(path_length(&theta) < grid);
+        assert!(path_length(&lazy) < grid);
+        assert!(lazy.sight_checks < theta.sight_checks);
+    }
+}
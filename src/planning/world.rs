@@ -1,4 +1,8 @@
 use std::fmt;
+use std::str::FromStr;
+
+use super::error::PlanningError;
+use super::map::{self, Map};
 
 /// Represent the state of a cell in the world
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -26,9 +30,38 @@ impl fmt::Display for Cell {
 
 impl Cell {
     fn clear(&mut self) {
+        if let Cell::Visited { .. } = self {
+            *self = Cell::Open
+        }
+    }
+}
+
+/// Ground type of a cell, scaling the cost of moving across it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Terrain {
+    Road,
+    Grass,
+    Mud,
+}
+
+impl fmt::Display for Terrain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Cell::Visited { g: _, h: _, k: _, parent: _ } => {*self = Cell::Open},
-            _ => {}
+            Terrain::Road  => write!(f, "Road"),
+            Terrain::Grass => write!(f, "Grass"),
+            Terrain::Mud   => write!(f, "Mud"),
+        }
+    }
+}
+
+impl Terrain {
+    /// Multiplier on the length of a move, never below 1 so the distance
+    /// heuristics stay admissible
+    pub fn cost(self) -> f32 {
+        match self {
+            Terrain::Road  => 1.0,
+            Terrain::Grass => 2.0,
+            Terrain::Mud   => 4.0,
         }
     }
 }
@@ -39,6 +72,7 @@ pub struct World {
     width: usize,
     height: usize,
     cells: Vec<Cell>,
+    terrain: Vec<Terrain>,
 }
 
 /// A way to describe neighbor strategies
@@ -48,6 +82,19 @@ pub enum Neighbors {
     CardinalAndDiagonal,
 }
 
+/// Parse "4" or "cardinal" and "8" or "diagonal"
+impl FromStr for Neighbors {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Neighbors, String> {
+        match s.to_lowercase().as_str() {
+            "4" | "cardinal" => Ok(Neighbors::Cardinal),
+            "8" | "diagonal" => Ok(Neighbors::CardinalAndDiagonal),
+            _ => Err(format!("unknown neighbors '{}'", s)),
+        }
+    }
+}
+
 /// Neighbor directions
 #[derive(Clone, Copy, Debug)]
 enum Neighbor {
@@ -72,9 +119,9 @@ impl NeighborIter {
     fn new(x: usize, y: usize, strat: Neighbors) -> NeighborIter {
         NeighborIter {
             next: Some(Neighbor::E),
-            strat: strat,
-            x: x,
-            y: y,
+            strat,
+            x,
+            y,
         }
     }
 }
@@ -160,16 +207,17 @@ pub type Id = usize;
 impl World {
 
     pub fn new(width: usize, height: usize, cells: Vec<Cell>) ->
-           Result<World, String>
+           Result<World, PlanningError>
     {
         if width * height != cells.len() {
-            Err("Width and height do not match number of cells".to_string())
+            Err(PlanningError::SizeMismatch { width, height, cells: cells.len() })
         } else {
             Ok(
                 World {
-                    width: width,
-                    height: height,
-                    cells: cells,
+                    width,
+                    height,
+                    terrain: vec![Terrain::Road; cells.len()],
+                    cells,
                 }
             )
         }
@@ -181,6 +229,23 @@ impl World {
         }
     }
 
+    /// Change the size of the world, keeping the cells that still fit and
+    /// filling any new space with open road
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let mut cells = vec![Cell::Open; width * height];
+        let mut terrain = vec![Terrain::Road; width * height];
+        for y in 0..height.min(self.height) {
+            for x in 0..width.min(self.width) {
+                cells[y * width + x] = self.cells[y * self.width + x];
+                terrain[y * width + x] = self.terrain[y * self.width + x];
+            }
+        }
+        self.width = width;
+        self.height = height;
+        self.cells = cells;
+        self.terrain = terrain;
+    }
+
     pub fn coords_for(&self, id: Id) -> Option<(usize, usize)>
     {
         if id < self.cells.len() {
@@ -239,11 +304,132 @@ impl World {
         }
     }
 
+    pub fn terrain(&self, id: Id) -> Option<Terrain> {
+        self.terrain.get(id).cloned()
+    }
+
+    pub fn set_terrain(&mut self, id: Id, terrain: Terrain) {
+        if let Some(t) = self.terrain.get_mut(id) {
+            *t = terrain;
+        }
+    }
+
+    /// True if both worlds have the same size, obstacles and terrain, whatever
+    /// the search has written into their cells
+    pub fn same_layout(&self, other: &World) -> bool {
+        self.width == other.width && self.height == other.height && self.terrain == other.terrain
+            && self.cells.iter().zip(other.cells.iter())
+                .all(|(a, b)| (*a == Cell::Obstacle) == (*b == Cell::Obstacle))
+    }
+
+    /// True if every cell has the same terrain
+    pub fn uniform_terrain(&self) -> bool {
+        self.terrain.windows(2).all(|w| w[0] == w[1])
+    }
+
+    /// Average terrain cost of the cells on the straight line between two
+    /// cells, so a move between neighbors costs the mean of the two
+    pub fn terrain_factor(&self, a: (usize, usize), b: (usize, usize)) -> f32 {
+        let mut total = 0.0;
+        let mut count = 0;
+        self.walk_line(a, b, |id| {
+            total += self.terrain[id].cost();
+            count += 1;
+            true
+        });
+        if count == 0 { 1.0 } else { total / count as f32 }
+    }
+
+    /// Length of the straight line between two cells scaled by its terrain
+    pub fn segment_cost(&self, a: Id, b: Id) -> Option<f32> {
+        let factor = self.terrain_factor(self.coords_for(a)?, self.coords_for(b)?);
+        Some(self.distance(a, b)? * factor)
+    }
+
+    /// Straight line distance between two cell centers
+    pub fn distance(&self, a: Id, b: Id) -> Option<f32> {
+        let (ax, ay) = self.coords_for(a)?;
+        let (bx, by) = self.coords_for(b)?;
+        let dx = ax.max(bx) - ax.min(bx);
+        let dy = ay.max(by) - ay.min(by);
+        Some(((dx * dx + dy * dy) as f32).sqrt())
+    }
+
+    /// Check the straight line between two cell centers crosses no obstacles
+    ///
+    /// Every cell the line passes through is checked. Where the line passes
+    /// exactly through a corner the cells on either side are not, matching
+    /// how diagonal moves are allowed to cut corners.
+    pub fn line_of_sight(&self, a: Id, b: Id) -> bool {
+        let a = match self.coords_for(a) { Some(c) => c, None => return false };
+        let b = match self.coords_for(b) { Some(c) => c, None => return false };
+        self.walk_line(a, b, |id| self.cells[id] != Cell::Obstacle)
+    }
+
+    /// Visit every cell the line between two cell centers passes through,
+    /// stopping early if `visit` returns false. Returns false if it stopped.
+    fn walk_line<F>(&self, a: (usize, usize), b: (usize, usize), mut visit: F) -> bool
+        where F: FnMut(Id) -> bool
+    {
+        let (x0, y0) = a;
+        let (x1, y1) = b;
+        let (mut x, mut y) = (x0 as isize, y0 as isize);
+        let dx = (x1 as isize - x).abs();
+        let dy = (y1 as isize - y).abs();
+        let x_inc = (x1 as isize - x).signum();
+        let y_inc = (y1 as isize - y).signum();
+        let mut error = dx - dy;
+        let mut n = 1 + dx + dy;
+        while n > 0 {
+            if let Some(id) = self.id_at(x as usize, y as usize) {
+                if !visit(id) {
+                    return false
+                }
+            }
+            if error > 0 {
+                x += x_inc;
+                error -= 2 * dy;
+            } else if error < 0 {
+                y += y_inc;
+                error += 2 * dx;
+            } else {
+                x += x_inc;
+                y += y_inc;
+                error += 2 * (dx - dy);
+                n -= 1;
+            }
+            n -= 1;
+        }
+        true
+    }
+
     pub fn width(&self) -> usize { self.width }
     pub fn height(&self) -> usize { self.height }
 
 }
 
+/// Parse map text, see `map` for the symbols. `S`, `G` and `*` are rejected
+/// so they aren't silently lost, parse a `Map` to keep them.
+impl FromStr for World {
+    type Err = PlanningError;
+
+    fn from_str(s: &str) -> Result<World, PlanningError> {
+        match map::parse_cells(s)? {
+            (Map { start: Some(_), .. }, _) => Err(PlanningError::MarkerInWorld('S')),
+            (Map { goal: Some(_), .. }, _) => Err(PlanningError::MarkerInWorld('G')),
+            (_, ref marked) if !marked.is_empty() => Err(PlanningError::MarkerInWorld('*')),
+            (map, _) => Ok(map.world),
+        }
+    }
+}
+
+/// Print as map text with visited cells marked `o`
+impl fmt::Display for World {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&map::render(self, None, None, &[]))
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -266,6 +452,33 @@ mod tests {
         assert_eq!(uut.id_at(4,3), None);
     }
 
+    #[test]
+    fn size_mismatch() {
+        assert_eq!(World::new(3, 2, vec![Cell::Open; 5]).err(),
+                   Some(PlanningError::SizeMismatch { width: 3, height: 2, cells: 5 }));
+    }
+
+    #[test]
+    fn resize() {
+        let mut uut = mkworld();
+        *uut.cell_at_mut(3,1).unwrap() = Cell::Obstacle;
+        *uut.cell_at_mut(1,3).unwrap() = Cell::Obstacle;
+        let mud = uut.id_at(2,1).unwrap();
+        uut.set_terrain(mud, Terrain::Mud);
+
+        uut.resize(6, 2);
+        assert_eq!((uut.width(), uut.height()), (6, 2));
+        assert_eq!(uut.cell_at(3,1), Some(&Cell::Obstacle));
+        assert_eq!(uut.cell_at(5,1), Some(&Cell::Open));
+        assert_eq!(uut.cell_at(1,3), None);
+        assert_eq!(uut.terrain(uut.id_at(2,1).unwrap()), Some(Terrain::Mud));
+
+        uut.resize(3, 4);
+        assert_eq!(uut.cell_at(3,1), None);
+        assert_eq!(uut.cell_at(1,3), Some(&Cell::Open));
+        assert_eq!(uut.terrain(uut.id_at(2,1).unwrap()), Some(Terrain::Mud));
+    }
+
     #[test]
     fn cell_at() {
         let uut = mkworld();
@@ -273,6 +486,38 @@ mod tests {
         assert_eq!(uut.cell_at(3,4), None);
     }
 
+    #[test]
+    fn line_of_sight() {
+        let mut uut = mkworld();
+        *uut.cell_at_mut(1,1).unwrap() = Cell::Obstacle;
+        let id = |x, y| uut.id_at(x, y).unwrap();
+        assert!(uut.line_of_sight(id(0,0), id(3,0)));
+        assert!(uut.line_of_sight(id(0,3), id(3,2)));
+        assert!(!uut.line_of_sight(id(0,0), id(3,3)));
+        assert!(!uut.line_of_sight(id(0,0), id(3,2)));
+        assert!(!uut.line_of_sight(id(1,0), id(1,3)));
+        assert!(!uut.line_of_sight(id(2,0), id(0,2)));
+        assert!(uut.line_of_sight(id(3,0), id(0,3)));
+        assert!(uut.line_of_sight(id(2,2), id(2,2)));
+        assert!((uut.distance(id(0,0), id(3,3)).unwrap() - 18.0f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn terrain_factor() {
+        let mut uut = mkworld();
+        let (mud, grass) = (uut.id_at(1,0).unwrap(), uut.id_at(2,2).unwrap());
+        assert!(uut.uniform_terrain());
+        uut.set_terrain(mud, Terrain::Mud);
+        uut.set_terrain(grass, Terrain::Grass);
+        assert!(!uut.uniform_terrain());
+        assert_eq!(uut.terrain(mud), Some(Terrain::Mud));
+        assert_eq!(uut.terrain_factor((0,0), (1,0)), 2.5);
+        assert_eq!(uut.terrain_factor((0,0), (0,1)), 1.0);
+        assert_eq!(uut.terrain_factor((0,0), (3,0)), 1.75);
+        assert_eq!(uut.terrain_factor((1,1), (2,2)), 1.5);
+        assert!((uut.segment_cost(5, grass).unwrap() - 1.5 * 2.0f32.sqrt()).abs() < 1e-6);
+    }
+
     #[test]
     fn neighbor_iter_all() {
         let mut ni = NeighborIter::new(1,1,Neighbors::CardinalAndDiagonal);
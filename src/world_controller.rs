@@ -4,65 +4,226 @@ use piston::input::GenericEvent;
 
 use planning::world::*;
 use planning::astar::*;
+use planning::planner::*;
+use planning::analysis::{self, HeuristicReport};
 
 pub enum AppState {
     Config {
         cfg: AStarCfg,
         world: World,
+        algorithm: Algorithm,
     },
-    Active(AStar),
+    Active(Box<dyn Planner>),
 }
 
 impl AppState {
 
     pub fn world(&self) -> &World {
         match &self {
-            AppState::Config { cfg: _, world } => &world,
-            AppState::Active(astar) => astar.world_view(),
+            AppState::Config { world, .. } => world,
+            AppState::Active(planner) => planner.world_view(),
+        }
+    }
+
+    pub fn config(&self) -> &AStarCfg {
+        match &self {
+            AppState::Config { cfg, .. } => cfg,
+            AppState::Active(planner) => planner.config_view(),
         }
     }
 
     pub fn goal(&self) -> Option<Id> {
         match &self {
-            AppState::Config { cfg, world: _ } => cfg.goal,
-            AppState::Active(astar) => Some(astar.goal()),
+            AppState::Config { cfg, .. } => cfg.goal,
+            AppState::Active(planner) => Some(planner.goal()),
         }
     }
 
     pub fn start(&self) -> Option<Id> {
         match &self {
-            AppState::Config { cfg, world: _ } => cfg.start,
-            AppState::Active(astar) => Some(astar.start()),
+            AppState::Config { cfg, .. } => cfg.start,
+            AppState::Active(planner) => Some(planner.start()),
+        }
+    }
+
+}
+
+/// What a left click paints onto the map while configuring
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Brush {
+    Obstacle,
+    Terrain(Terrain),
+}
+
+impl Brush {
+    pub fn next(self) -> Brush {
+        match self {
+            Brush::Obstacle                => Brush::Terrain(Terrain::Road),
+            Brush::Terrain(Terrain::Road)  => Brush::Terrain(Terrain::Grass),
+            Brush::Terrain(Terrain::Grass) => Brush::Terrain(Terrain::Mud),
+            Brush::Terrain(Terrain::Mud)   => Brush::Obstacle,
+        }
+    }
+}
+
+/// Playback speeds offered in the sidebar, in steps per second
+const SPEEDS: [f64; 7] = [1.0, 2.0, 5.0, 10.0, 30.0, 100.0, 1000.0];
+
+/// Most steps "Run to end" takes before giving up, IDA* can take a very long
+/// time to prove there is no path
+const RUN_LIMIT: usize = 1_000_000;
+
+/// Steps "Run to end" takes per update, so the window keeps drawing and
+/// answering clicks during a long run
+const RUN_STEPS_PER_UPDATE: usize = 2_000;
+
+/// Most snapshots between two full copies of the planner, a copy holds the
+/// whole world so keeping one every step runs out of memory on big maps
+const COPY_INTERVAL: usize = 200;
+
+/// Most snapshots the timeline keeps, the oldest are dropped past this
+const MAX_HISTORY: usize = 20_000;
+
+/// Smallest and largest number of rows or columns the sidebar allows
+const MIN_SIDE: usize = 2;
+pub const MAX_SIDE: usize = 100;
+
+/// One more or one less row or column, within the sidebar limits. Sides
+/// already past a limit, as a loaded map can be, are never pushed further
+/// out or pulled back in.
+fn step_side(side: usize, grow: bool) -> usize {
+    if grow {
+        if side >= MAX_SIDE { side } else { side + 1 }
+    } else if side <= MIN_SIDE {
+        side
+    } else {
+        side - 1
+    }
+}
+
+/// Rows of controls that fit beside the board
+const CONTROL_ROWS: f64 = 12.0;
+
+/// Where things go on screen for a world of some size, shared by the
+/// controller and the view so clicks land on what was drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    /// Side of one square cell
+    pub cell_size: f64,
+    /// Width and height of the board
+    pub board: [f64; 2],
+    /// Height of one control in the sidebar
+    pub row_height: f64,
+    pub sidebar_width: f64,
+}
+
+impl Layout {
+    /// Fit the world into a square of side `size`
+    pub fn new(world: &World, size: f64) -> Layout {
+        let cell_size = size / world.width().max(world.height()) as f64;
+        let row_height = size / CONTROL_ROWS;
+        Layout {
+            cell_size,
+            board: [cell_size * world.width() as f64, cell_size * world.height() as f64],
+            row_height,
+            sidebar_width: row_height * 3.6,
+        }
+    }
+
+    /// Cell under a point measured from the board's upper left corner
+    pub fn cell_at(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        if x >= 0.0 && x < self.board[0] && y >= 0.0 && y < self.board[1] {
+            Some(((x / self.cell_size) as usize, (y / self.cell_size) as usize))
+        } else {
+            None
         }
     }
+}
 
+/// Planner state after some number of steps, kept so the timeline can jump
+/// back to it. Only some snapshots hold a copy of the planner, the others are
+/// rebuilt by stepping forward from the closest copy before them.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub planner: Option<Box<dyn Planner>>,
+    /// Steps taken since the previous snapshot
+    pub steps: usize,
+    pub step: usize,
+    pub outcome: Option<StepResult>,
+    /// How much of the log had been written
+    pub log_len: usize,
 }
 
-/// Handles events for Sudoku game.
+/// Handles events for the path visualizer.
 pub struct WorldController {
     /// Determines current state
     pub state: AppState,
     /// What step in the process
     pub step: usize,
+    /// What the last step that did any work reported
+    pub outcome: Option<StepResult>,
+    /// Events from every step so far, tagged with the step they happened in
+    pub log: Vec<(usize, StepEvent)>,
+    /// How many lines the log panel is scrolled back from the newest
+    pub log_scroll: usize,
+    /// Every state the active planner has been in, oldest first
+    pub history: Vec<Snapshot>,
+    /// Which entry of the history is being shown
+    pub history_pos: usize,
+    /// Step automatically on update events
+    pub playing: bool,
+    /// Playback speed while playing
+    pub steps_per_second: f64,
+    /// Fraction of a step carried over between updates
+    step_budget: f64,
+    /// "Run to end" is searching a batch of steps on every update
+    pub running: bool,
+    /// Steps taken by the current run
+    run_steps: usize,
     /// Selected cell.
     pub selected_cell: Option<(usize, usize)>,
     /// Stores last mouse cursor position.
     pub cursor_pos: [f64; 2],
+    /// What left clicks paint in config state
+    pub brush: Brush,
+    /// Highlight cells where the heuristic overestimates or is inconsistent
+    pub check_heuristic: bool,
+    /// Result of the last heuristic check, None while checking is off or the
+    /// config can't be checked
+    pub heuristic_report: Option<HeuristicReport>,
 }
 
 impl WorldController {
     /// Creates a new world controller.
-    pub fn new(world_side_len: usize) -> WorldController {
+    pub fn new(width: usize, height: usize) -> WorldController {
+        let world = World::new(width, height, vec![Cell::Open; width*height]).unwrap();
+        WorldController::with_world(world, AStarCfg::new())
+    }
+
+    /// Creates a world controller configuring a search on the given world.
+    pub fn with_world(world: World, cfg: AStarCfg) -> WorldController {
         WorldController {
             state: AppState::Config{ 
-                cfg: AStarCfg::new(), 
-                world: World::new(
-                    world_side_len,
-                    world_side_len,
-                    vec![Cell::Open; world_side_len*world_side_len]).unwrap()},
+                cfg,
+                world,
+                algorithm: Algorithm::AStar,
+            },
             step: 0,
+            outcome: None,
+            log: Vec::new(),
+            log_scroll: 0,
+            history: Vec::new(),
+            history_pos: 0,
+            playing: false,
+            steps_per_second: SPEEDS[2],
+            step_budget: 0.0,
+            running: false,
+            run_steps: 0,
             selected_cell: None,
             cursor_pos: [0.0, 1.0],
+            brush: Brush::Obstacle,
+            check_heuristic: false,
+            heuristic_report: None,
         }
     }
 
@@ -70,37 +231,225 @@ impl WorldController {
         self.state.world()
     }
 
+    /// Check the heuristic again, needed whenever the map, start, goal or the
+    /// parts of the config it depends on change
+    fn refresh_report(&mut self) {
+        self.heuristic_report = if self.check_heuristic {
+            analysis::check_heuristic(self.state.config(), self.world()).ok()
+        } else {
+            None
+        };
+    }
+
+    /// Log lines written up to the step being shown
+    pub fn visible_log(&self) -> &[(usize, StepEvent)] {
+        match self.history.get(self.history_pos) {
+            Some(snapshot) => &self.log[..snapshot.log_len],
+            None => &self.log,
+        }
+    }
+
+    /// Remember the active planner as the newest entry in the history.
+    /// `steps` is how many steps it took since the previous entry, None when
+    /// it can't be reached by stepping and needs a copy.
+    fn record(&mut self, steps: Option<usize>) {
+        if let AppState::Active(planner) = &self.state {
+            let since_copy = self.history.iter().rev()
+                .take_while(|snapshot| snapshot.planner.is_none())
+                .count();
+            let copy = steps.is_none() || since_copy + 1 >= COPY_INTERVAL;
+            self.history.push(Snapshot {
+                planner: if copy { Some(planner.clone()) } else { None },
+                steps: steps.unwrap_or(0),
+                step: self.step,
+                outcome: self.outcome,
+                log_len: self.log.len(),
+            });
+            if self.history.len() > MAX_HISTORY {
+                // the oldest entry left has to be a copy to rebuild the rest from
+                let drop = self.history.iter().skip(1)
+                    .position(|snapshot| snapshot.planner.is_some())
+                    .map_or(0, |i| i + 1);
+                self.history.drain(..drop);
+            }
+            self.history_pos = self.history.len() - 1;
+        }
+    }
+
+    /// Drop any history after the current entry and remember the active
+    /// planner after it, for when an edit makes the planner diverge
+    fn branch(&mut self) {
+        self.history.truncate(self.history_pos + 1);
+        let log_len = self.history.last().map_or(0, |snapshot| snapshot.log_len);
+        self.log.truncate(log_len);
+        self.record(None);
+    }
+
+    /// Show an earlier or later entry of the history
+    fn jump_to(&mut self, pos: usize) {
+        if pos >= self.history.len() {
+            return
+        }
+        let copy = (0..pos + 1).rev()
+            .find(|&i| self.history[i].planner.is_some())
+            .expect("history starts with a copy");
+        // moving forward with no copy on the way can keep stepping the shown planner
+        let shown = matches!(self.state, AppState::Active(_))
+            && copy <= self.history_pos && self.history_pos <= pos;
+        let mut edited = false;
+        let from = if shown {
+            self.history_pos
+        } else {
+            let planner = self.history[copy].planner.clone().unwrap();
+            // crossing an obstacle edit changes what the heuristic is checked against
+            edited = !self.world().same_layout(planner.world_view());
+            self.state = AppState::Active(planner);
+            copy
+        };
+        if let AppState::Active(planner) = &mut self.state {
+            for snapshot in &self.history[from + 1..pos + 1] {
+                for _ in 0..snapshot.steps {
+                    planner.step();
+                }
+            }
+        }
+        let snapshot = &self.history[pos];
+        self.step = snapshot.step;
+        self.outcome = snapshot.outcome;
+        self.history_pos = pos;
+        self.log_scroll = 0;
+        if edited {
+            self.refresh_report();
+        }
+    }
+
+    /// Step the active planner and log what it did, without touching the
+    /// history
+    fn take_step(&mut self) -> StepResult {
+        let result = match &mut self.state {
+            AppState::Active(planner) => planner.step(),
+            AppState::Config { .. } => return StepResult::AlreadyFinished,
+        };
+        if let Some(step) = result.step() {
+            self.step = step;
+            self.outcome = Some(result);
+            if let AppState::Active(planner) = &self.state {
+                self.log.extend(planner.events().iter().map(|&event| (step, event)));
+            }
+        }
+        result
+    }
+
+    /// Move one step forward, replaying the history if that step was already
+    /// taken. Returns false once the search has nothing left to do.
+    fn advance(&mut self) -> bool {
+        if self.history_pos + 1 < self.history.len() {
+            let pos = self.history_pos + 1;
+            self.jump_to(pos);
+            return true
+        }
+        let result = self.take_step();
+        if result.step().is_some() {
+            self.record(Some(1));
+        }
+        result.expanded()
+    }
+
+    /// Start searching until done, a batch of steps on each update. Only the
+    /// final state goes in the history.
+    fn run_to_end(&mut self) {
+        if let Some(last) = self.history.len().checked_sub(1) {
+            self.jump_to(last);
+        }
+        self.running = true;
+        self.run_steps = 0;
+    }
+
+    /// Take the next batch of steps of a run
+    fn continue_run(&mut self) {
+        for _ in 0..RUN_STEPS_PER_UPDATE {
+            let result = self.take_step();
+            if result.step().is_some() {
+                self.run_steps += 1;
+            }
+            if !result.expanded() || self.run_steps >= RUN_LIMIT {
+                self.finish_run();
+                return
+            }
+        }
+    }
+
+    /// Stop a run where it is and record how far it got
+    fn finish_run(&mut self) {
+        if !self.running {
+            return
+        }
+        self.running = false;
+        // a copy, the run may be too long to replay
+        if self.run_steps > 0 {
+            self.record(None);
+        }
+    }
+
     /// Handles events.
     pub fn event<E: GenericEvent>(&mut self, pos: [f64; 2], size: f64, e: &E) {
-        use piston::input::{Button, Key, MouseButton};
+        use piston::input::{Button, MouseButton};
 
-        // layout unit of measure
-        let cell_size = size / self.world().width() as f64;
+        let layout = Layout::new(self.world(), size);
+        let mut recheck = false;
   
         if let Some(pos) = e.mouse_cursor_args() {
             self.cursor_pos = pos;
         }
+        // any click ends a run, recording it before anything else changes
+        let was_running = self.running;
+        if e.press_args().is_some() {
+            self.finish_run();
+        }
+        if let Some(args) = e.update_args() {
+            if self.running {
+                self.continue_run();
+            }
+            if self.playing {
+                self.step_budget += args.dt * self.steps_per_second;
+                while self.step_budget >= 1.0 {
+                    self.step_budget -= 1.0;
+                    if !self.advance() {
+                        self.playing = false;
+                        self.step_budget = 0.0;
+                    }
+                }
+            }
+        }
+        // scrolling anywhere over the sidebar moves through the log
+        if let Some([_, dy]) = e.mouse_scroll_args() {
+            if self.cursor_pos[0] - pos[0] >= size {
+                self.log_scroll = if dy > 0.0 {
+                    (self.log_scroll + 1).min(self.visible_log().len())
+                } else {
+                    self.log_scroll.saturating_sub(1)
+                };
+            }
+        }
         if let Some(Button::Mouse(MouseButton::Right)) = e.press_args() {
             // Find coordinates relative to upper left corner.
             let x = self.cursor_pos[0] - pos[0];
             let y = self.cursor_pos[1] - pos[1];
-            // Check that coordinates are inside board boundaries.
-            if x >= 0.0 && x <= size && y >= 0.0 && y <= size {
-                // Compute the cell position.
-                let cell_x = (x / size * self.world().width() as f64) as usize;
-                let cell_y = (y / size * self.world().height() as f64) as usize;
+            // Compute the cell position if inside board boundaries.
+            if let Some((cell_x, cell_y)) = layout.cell_at(x, y) {
                 self.selected_cell = Some((cell_x, cell_y));
                 match &mut self.state {
-                    AppState::Config { cfg, world} => {
+                    AppState::Config { cfg, world, .. } => {
                         // Set goal then start
-                        if cfg.start == None {
+                        if cfg.start.is_none() {
                             cfg.start = world.id_at(cell_x, cell_y);
-                        } else if cfg.goal == None {
+                        } else if cfg.goal.is_none() {
                             cfg.goal = world.id_at(cell_x, cell_y);
                         } else {
                             cfg.start = None;
                             cfg.goal = None;
                         }
+                        recheck = true;
                     }
                     AppState::Active(_) => {}
                 };
@@ -112,99 +461,295 @@ impl WorldController {
             // Find coordinates relative to upper left corner.
             let x = self.cursor_pos[0] - pos[0];
             let y = self.cursor_pos[1] - pos[1];
-            // Check that coordinates are inside board boundaries.
-            if x >= 0.0 && x <= size && y >= 0.0 && y <= size {
-                // Compute the cell position.
-                let cell_x = (x / size * self.world().width() as f64) as usize;
-                let cell_y = (y / size * self.world().height() as f64) as usize;
+            // Compute the cell position if inside board boundaries.
+            if let Some((cell_x, cell_y)) = layout.cell_at(x, y) {
                 self.selected_cell = Some((cell_x, cell_y));
                 match &mut self.state {
-                    AppState::Config { cfg: _, world} => {
-                        // Toggle obstacle if in config state
-                        if let Some(cell) = world.cell_at_mut(cell_x, cell_y) {
-                            *cell = match cell {
-                                Cell::Obstacle => Cell::Open,
-                                _              => Cell::Obstacle,
+                    AppState::Config { world, .. } => {
+                        // Toggle obstacle or paint terrain if in config state
+                        let id = world.id_at(cell_x, cell_y);
+                        match (self.brush, id) {
+                            (Brush::Obstacle, Some(id)) => {
+                                let cell = world.cell_mut(id).unwrap();
+                                *cell = match cell {
+                                    Cell::Obstacle => Cell::Open,
+                                    _              => Cell::Obstacle,
+                                }
+                            }
+                            (Brush::Terrain(terrain), Some(id)) => {
+                                *world.cell_mut(id).unwrap() = Cell::Open;
+                                world.set_terrain(id, terrain);
+                            }
+                            _ => {}
+                        }
+                        recheck = true;
+                    }
+                    AppState::Active(planner) => {
+                        // Replanning algorithms can keep going after an edit
+                        if let Some(id) = planner.world_view().id_at(cell_x, cell_y) {
+                            if planner.toggle_obstacle(id) {
+                                self.outcome = None;
+                                recheck = true;
+                                // the edit starts a new branch from this step
+                                self.branch();
                             }
                         }
                     }
-                    AppState::Active(_) => {}
                 };
 
             }
             // Check that coordinates are inside controls boundaries.
-            if x >= size && x <= size + cell_size * 3.0 && y >= 0.0 && y <= size {
-                // Compute the cell position.
-                let ctrl_index = (y / cell_size) as usize;
+            if x >= size && x <= size + layout.sidebar_width && y >= 0.0 && y <= size {
+                // Compute the control position.
+                let ctrl_index = (y / layout.row_height) as usize;
+                // some controls do different things on their left and right halves
+                let right_half = x - size > layout.sidebar_width * 0.5;
                 let mut toggle_state = false;
+                let mut jump = None;
+                let mut advance = false;
+                let mut run_to_end = false;
                 match &mut self.state {
-                    AppState::Config{cfg, world} => {
+                    AppState::Config{cfg, world, algorithm} => {
                         match ctrl_index {
-                            0 => match cfg.neighbors {
+                            0 => {
+                                *algorithm = algorithm.next();
+                                // the weight control is off for the rest, don't leave it stuck above 1
+                                if !algorithm.uses_weight() {
+                                    cfg.weight = 1.0;
+                                }
+                            },
+                            1 => match cfg.neighbors {
                                 Neighbors::CardinalAndDiagonal => cfg.neighbors = Neighbors::Cardinal,
                                 Neighbors::Cardinal => cfg.neighbors = Neighbors::CardinalAndDiagonal,
                             },
-                            1 => match cfg.heuristic {
-                                Some(Heuristic::Manhattan) => cfg.heuristic = None,
-                                Some(Heuristic::Euclidean) => cfg.heuristic = Some(Heuristic::Manhattan),
-                                None => cfg.heuristic = Some(Heuristic::Euclidean),
+                            2 => cfg.heuristic = Heuristic::next(&cfg.heuristic),
+                            // step the weight up to 5 then wrap back to 1
+                            3 if algorithm.uses_weight() => cfg.weight = if cfg.weight >= 5.0 { 1.0 } else { cfg.weight + 0.5 },
+                            4 => cfg.tie_break = cfg.tie_break.next(),
+                            5 => cfg.direction = match cfg.direction {
+                                Direction::Backward => Direction::Forward,
+                                Direction::Forward => Direction::Backward,
                             },
-                            2 => {
-                                if let Ok(_) = cfg.valid_for(&world) {
-                                    toggle_state = true;
-                                }
+                            6 => self.brush = self.brush.next(),
+                            7 => self.check_heuristic = !self.check_heuristic,
+                            8 | 9 => {
+                                // left half shrinks, right half grows
+                                let resize = |side: usize| step_side(side, right_half);
+                                let (width, height) = if ctrl_index == 8 {
+                                    (resize(world.width()), world.height())
+                                } else {
+                                    (world.width(), resize(world.height()))
+                                };
+                                // ids move when the width changes, keep start and goal where they were
+                                let remap = |id: Option<Id>| id
+                                    .and_then(|id| world.coords_for(id))
+                                    .and_then(|(x, y)| if x < width && y < height {
+                                        Some(y * width + x)
+                                    } else { None });
+                                cfg.start = remap(cfg.start);
+                                cfg.goal = remap(cfg.goal);
+                                world.resize(width, height);
                             },
+                            10 => toggle_state = true,
                             _ => {},
                         };
+                        // the algorithm, weight, ties and brush don't change the check
+                        recheck = matches!(ctrl_index, 1 | 2 | 5 | 7 | 8 | 9);
                     },
-                    AppState::Active(astar) => {
+                    AppState::Active(planner) => {
                         match ctrl_index {
-                            0 => { if let Some(step) = astar.step() { self.step = step; } },
-                            1 => {
+                            0 => advance = true,
+                            1 => jump = self.history_pos.checked_sub(1),
+                            2 => {
                                 toggle_state = true;
                             },
+                            // timeline, click along it to pick a step
+                            3 if self.history.len() > 1 => {
+                                let along = ((x - size) / layout.sidebar_width).clamp(0.0, 1.0);
+                                jump = Some((along * (self.history.len() - 1) as f64).round() as usize);
+                            },
+                            4 => self.playing = !self.playing,
+                            5 => {
+                                let idx = SPEEDS.iter().position(|&s| s == self.steps_per_second);
+                                self.steps_per_second = SPEEDS[idx.map_or(0, |i| (i + 1) % SPEEDS.len())];
+                            },
+                            // the same button stops a run
+                            6 => run_to_end = !was_running,
+                            // cycle through the paths kept by anytime planners
+                            7 => if let Some(idx) = planner.selected_solution() {
+                                let next = (idx + 1) % planner.solution_count();
+                                planner.select_solution(next);
+                            },
                             _ => {},
                         };
                     }
                 };
+                if advance {
+                    self.advance();
+                }
+                if run_to_end {
+                    self.playing = false;
+                    self.run_to_end();
+                }
+                if let Some(pos) = jump {
+                    // looking back at a step pauses playback
+                    self.playing = false;
+                    self.jump_to(pos);
+                }
                 if toggle_state {
                     let new_state = match &self.state {
-                        AppState::Config{cfg, world} => AppState::Active(
-                            AStar::from_cfg(cfg.clone(),
-                            world.clone()).unwrap()
-                        ),
-                        AppState::Active(astar) => {
-                            let mut new_world = (*astar.world_view()).clone();
+                        AppState::Config{cfg, world, algorithm} => {
+                            match algorithm.build(cfg.clone(), world.clone()) {
+                                Ok(planner) => AppState::Active(planner),
+                                // the status message already says what is wrong
+                                Err(_) => return,
+                            }
+                        },
+                        AppState::Active(planner) => {
+                            let mut new_world = (*planner.world_view()).clone();
                             new_world.clear();
                             AppState::Config{
-                                cfg: AStarCfg::new()
-                                        .with_goal(astar.goal())
-                                        .with_start(astar.start()), 
+                                cfg: planner.config_view().clone(),
                                 world: new_world,
+                                algorithm: planner.algorithm(),
                             }
                         },
                     };
                     self.state = new_state;
+                    self.outcome = None;
+                    self.step = 0;
+                    self.playing = false;
+                    self.log.clear();
+                    self.log_scroll = 0;
+                    self.history.clear();
+                    self.record(None);
                 }
             }
         }
-        if let Some(Button::Keyboard(key)) = e.press_args() {
-            if let Some(ind) = self.selected_cell {
-                // Set cell value.
-                match key {
-//                    Key::D1 => self.world.set(ind, 1),
-//                    Key::D2 => self.world.set(ind, 2),
-//                    Key::D3 => self.world.set(ind, 3),
-//                    Key::D4 => self.world.set(ind, 4),
-//                    Key::D5 => self.world.set(ind, 5),
-//                    Key::D6 => self.world.set(ind, 6),
-//                    Key::D7 => self.world.set(ind, 7),
-//                    Key::D8 => self.world.set(ind, 8),
-//                    Key::D9 => self.world.set(ind, 9),
-                    _ => {}
-                }
-            }
+        if recheck {
+            self.refresh_report();
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn mkcontroller() -> WorldController {
+        let mut controller = WorldController::new(6, 6);
+        let world = controller.world().clone();
+        let cfg = AStarCfg::new().with_goal(0).with_start(35);
+        controller.state = AppState::Active(Algorithm::AStar.build(cfg, world).unwrap());
+        controller.record(None);
+        controller
+    }
+
+    #[test]
+    fn resizing_keeps_oversized_sides() {
+        assert_eq!(step_side(10, true), 11);
+        assert_eq!(step_side(10, false), 9);
+        assert_eq!(step_side(MAX_SIDE, true), MAX_SIDE);
+        assert_eq!(step_side(MIN_SIDE, false), MIN_SIDE);
+        // a loaded map outside the limits keeps its cells
+        assert_eq!(step_side(150, true), 150);
+        assert_eq!(step_side(150, false), 149);
+        assert_eq!(step_side(1, false), 1);
+        assert_eq!(step_side(1, true), 2);
+    }
+
+    #[test]
+    fn layout_fits_rectangular_worlds() {
+        let world = World::new(8, 4, vec![Cell::Open; 32]).unwrap();
+        let layout = Layout::new(&world, 640.0);
+        assert_eq!(layout.cell_size, 80.0);
+        assert_eq!(layout.board, [640.0, 320.0]);
+        assert_eq!(layout.cell_at(0.0, 0.0), Some((0, 0)));
+        assert_eq!(layout.cell_at(639.0, 319.0), Some((7, 3)));
+        assert_eq!(layout.cell_at(100.0, 330.0), None);
+        assert_eq!(layout.cell_at(-1.0, 10.0), None);
+    }
+
+    #[test]
+    fn history_keeps_sparse_copies() {
+        // more steps than the history holds
+        let world = World::new(150, 150, vec![Cell::Open; 22500]).unwrap();
+        let cfg = AStarCfg::new().with_goal(0).with_start(22499);
+        let mut controller = WorldController::with_world(world.clone(), cfg.clone());
+        controller.state = AppState::Active(Algorithm::Dijkstra.build(cfg.clone(), world.clone()).unwrap());
+        controller.record(None);
+        while controller.advance() {}
+        assert!(controller.history.len() <= MAX_HISTORY);
+        assert!(controller.history[0].planner.is_some());
+        let copies = controller.history.iter().filter(|snapshot| snapshot.planner.is_some()).count();
+        assert!(copies <= MAX_HISTORY / COPY_INTERVAL + 1);
+
+        // a rebuilt entry matches the planner that was there the first time
+        for &pos in [COPY_INTERVAL / 2, controller.history.len() / 2].iter() {
+            controller.jump_to(pos);
+            let mut fresh = Algorithm::Dijkstra.build(cfg.clone(), world.clone()).unwrap();
+            for _ in 0..controller.step {
+                fresh.step();
+            }
+            if let AppState::Active(planner) = &controller.state {
+                assert_eq!(planner.current(), fresh.current());
+                assert_eq!(planner.frontier_view(), fresh.frontier_view());
+            }
+        }
+    }
+
+    #[test]
+    fn rechecks_heuristic_across_edits() {
+        let mut controller = WorldController::new(6, 6);
+        let world = controller.world().clone();
+        let cfg = AStarCfg::new().with_goal(0).with_start(35)
+                    .with_hueristic(Some(Heuristic::Euclidean));
+        controller.state = AppState::Active(Algorithm::LpaStar.build(cfg, world).unwrap());
+        controller.record(None);
+        controller.check_heuristic = true;
+        controller.refresh_report();
+        assert!(controller.heuristic_report.as_ref().unwrap().admissible());
+
+        controller.advance();
+        if let AppState::Active(planner) = &mut controller.state {
+            assert!(planner.toggle_obstacle(14));
+        }
+        controller.branch();
+        controller.refresh_report();
+        assert!(controller.heuristic_report.as_ref().unwrap().cost_to_go[14].is_infinite());
+
+        // stepping back before the edit checks against the old map again
+        controller.jump_to(0);
+        assert!(controller.heuristic_report.as_ref().unwrap().cost_to_go[14].is_finite());
+    }
+
+    #[test]
+    fn replays_history_after_run_to_end() {
+        let mut controller = mkcontroller();
+        controller.advance();
+        controller.advance();
+        assert_eq!(controller.history.len(), 3);
+
+        // the run only keeps its final state
+        controller.run_to_end();
+        while controller.running {
+            controller.continue_run();
+        }
+        let end = controller.history.len() - 1;
+        assert_eq!(end, 3);
+        assert!(matches!(controller.outcome, Some(StepResult::Found { .. })));
+        let log_len = controller.log.len();
+
+        // stepping back and forward again replays without searching
+        controller.jump_to(1);
+        assert_eq!(controller.step, 1);
+        assert!(controller.visible_log().len() < log_len);
+        assert!(controller.advance());
+        assert_eq!(controller.history_pos, 2);
+        assert_eq!(controller.log.len(), log_len);
+        controller.jump_to(end);
+        assert!(!controller.advance());
+        assert_eq!(controller.history.len(), end + 1);
+    }
+}
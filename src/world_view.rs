@@ -5,9 +5,11 @@ use graphics::{Context, Graphics};
 use graphics::character::CharacterCache;
 
 use WorldController;
+use world_controller::{Brush, Layout};
 use AppState;
 use planning::world::*;
-use planning::astar::*;
+use planning::planner::{Highlight, StepResult};
+use planning::error::PlanningError;
 
 /// Stores world view settings.
 pub struct WorldViewSettings {
@@ -28,6 +30,31 @@ pub struct WorldViewSettings {
     pub path_line_color: Color,
     pub path_line_radius: f64,
     pub visited_cell_color: Color,
+    /// Darkens cells by this alpha for each unit of terrain cost above 1
+    pub terrain_shade_color: Color,
+    pub updated_cell_color: Color,
+    pub inconsistent_cell_color: Color,
+    pub jump_point_color: Color,
+    pub pruned_cell_color: Color,
+    pub forward_visited_color: Color,
+    pub backward_visited_color: Color,
+    pub meeting_cell_color: Color,
+    pub stack_cell_color: Color,
+    pub overestimate_cell_color: Color,
+    pub triangle_break_cell_color: Color,
+    /// Strip across the board shown when the search finds no path
+    pub banner_color: Color,
+    pub log_background_color: Color,
+    /// Knob marking the current step on the timeline
+    pub timeline_color: Color,
+    /// Lines of the step log shown at once
+    pub log_lines: usize,
+}
+
+impl Default for WorldViewSettings {
+    fn default() -> WorldViewSettings {
+        WorldViewSettings::new()
+    }
 }
 
 impl WorldViewSettings {
@@ -51,6 +78,21 @@ impl WorldViewSettings {
             path_line_color: [1.0, 0.5, 1.0, 1.0],
             path_line_radius: 5.0,
             visited_cell_color: [1.0, 0.9, 1.0, 1.0],
+            terrain_shade_color: [0.4, 0.25, 0.1, 0.15],
+            updated_cell_color: [1.0, 0.8, 0.2, 0.5],
+            inconsistent_cell_color: [1.0, 0.3, 0.3, 0.4],
+            jump_point_color: [0.2, 0.7, 0.9, 0.4],
+            pruned_cell_color: [0.1, 0.1, 0.1, 0.3],
+            forward_visited_color: [0.4, 0.4, 1.0, 0.3],
+            backward_visited_color: [0.4, 1.0, 0.4, 0.3],
+            meeting_cell_color: [1.0, 0.6, 0.0, 0.7],
+            stack_cell_color: [0.6, 0.2, 0.8, 0.4],
+            overestimate_cell_color: [0.9, 0.0, 0.0, 0.5],
+            triangle_break_cell_color: [1.0, 0.5, 0.0, 0.4],
+            banner_color: [0.9, 0.3, 0.3, 0.9],
+            log_background_color: [0.95, 0.95, 1.0, 1.0],
+            timeline_color: [1.0, 0.5, 1.0, 1.0],
+            log_lines: 10,
         }
     }
 }
@@ -65,7 +107,7 @@ impl WorldView {
     /// Creates a new world view.
     pub fn new(settings: WorldViewSettings) -> WorldView {
         WorldView {
-            settings: settings,
+            settings,
         }
     }
 
@@ -97,7 +139,7 @@ impl WorldView {
                         glyphs,
                         &c.draw_state,
                         c.transform.trans(x + 10.0, y + 0.5 * (sy + self.settings.font_size as f64)),
-                        g);
+                        g).ok();
     }
 
     fn write_anywhere <G: Graphics, C>(
@@ -118,9 +160,10 @@ impl WorldView {
                         glyphs,
                         &c.draw_state,
                         c.transform.trans(x, y),
-                        g);
+                        g).ok();
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn write_cell<G: Graphics, C>(
       &self,
       cell_size: f64,
@@ -143,7 +186,7 @@ impl WorldView {
                         &c.draw_state,
                         c.transform.trans(pos[0] + self.settings.position[0],
                                           pos[1] + self.settings.position[1]),
-                        g);
+                        g).ok();
     }
 
     /// Draw world.
@@ -158,20 +201,24 @@ impl WorldView {
     {
         use graphics::{Line, Rectangle, Text, Transformed};
 
-        let ref settings = self.settings;
+        let settings = &self.settings;
+        let layout = Layout::new(controller.world(), settings.size);
+        let cell_size = layout.cell_size;
+        let [board_width, board_height] = layout.board;
         let board_rect = [
             settings.position[0], settings.position[1],
-            settings.size, settings.size,
+            board_width, board_height,
         ];
+        // leave the per cell numbers out once they no longer fit
+        let cell_text = cell_size >= (settings.font_size * 4) as f64;
 
         // Draw board background.
         Rectangle::new(settings.background_color)
             .draw(board_rect, &c.draw_state, c.transform, g);
 
-        let cell_size = settings.size / controller.world().width() as f64;
-
-        // Number cells
-        for j in 0..controller.world().height() {
+        // Number cells, skipping some once they would overlap
+        let label_every = ((settings.font_size * 2) as f64 / cell_size).ceil().max(1.0) as usize;
+        for j in (0..controller.world().height()).step_by(label_every) {
             let pos = [ 0.0 - self.settings.font_size as f64, j as f64 * cell_size + (self.settings.font_size*2) as f64];
             let text_image = Text::new(self.settings.font_size);
             text_image.draw(&format!("{}",j),
@@ -179,9 +226,9 @@ impl WorldView {
                             &c.draw_state,
                             c.transform.trans(pos[0] + self.settings.position[0],
                                               pos[1] + self.settings.position[1]),
-                            g);
+                            g).ok();
         }
-        for j in 0..controller.world().width() {
+        for j in (0..controller.world().width()).step_by(label_every) {
             let pos = [ j as f64 * cell_size + (self.settings.font_size*2) as f64, 0.0 - self.settings.board_edge_radius * 2.0];
             let text_image = Text::new(self.settings.font_size);
             text_image.draw(&format!("{}",j),
@@ -189,7 +236,7 @@ impl WorldView {
                             &c.draw_state,
                             c.transform.trans(pos[0] + self.settings.position[0],
                                               pos[1] + self.settings.position[1]),
-                            g);
+                            g).ok();
         }
 
         // Draw cells.
@@ -211,29 +258,75 @@ impl WorldView {
                 };
                 Rectangle::new(color).draw(cell_rect, &c.draw_state, c.transform, g);
 
+                // shade expensive terrain so detours around it make sense
+                let terrain_cost = controller.world().terrain(cell_id).unwrap().cost();
+                if *cell != Cell::Obstacle && terrain_cost > 1.0 {
+                    let mut shade = settings.terrain_shade_color;
+                    shade[3] *= terrain_cost - 1.0;
+                    Rectangle::new(shade).draw(cell_rect, &c.draw_state, c.transform, g);
+                }
+
                 // Mark start and goal
                 if let Some(start) = controller.state.start() {
                     if start == cell_id {
                         Rectangle::new(settings.start_color).draw(cell_rect, &c.draw_state, c.transform, g);
-                        self.write_cell(cell_size, (i,j), (cell_size - settings.font_size as f64,0.0),
-                                        "S", glyphs, c, g);                     
+                        if cell_size >= settings.font_size as f64 {
+                            self.write_cell(cell_size, (i,j), (cell_size - settings.font_size as f64,0.0),
+                                            "S", glyphs, c, g);
+                        }
                     }
                 }
                 if let Some(goal) = controller.state.goal() {
                     if goal == cell_id {
                         Rectangle::new(settings.goal_color).draw(cell_rect, &c.draw_state, c.transform, g);
-                        self.write_cell(cell_size, (i,j), (cell_size - settings.font_size as f64,0.0),
-                                        "G", glyphs, c, g);                     
+                        if cell_size >= settings.font_size as f64 {
+                            self.write_cell(cell_size, (i,j), (cell_size - settings.font_size as f64,0.0),
+                                            "G", glyphs, c, g);
+                        }
                     }
                 }
 
             }
         }
 
+        // Draw algorithm specific markings and heuristic problems
+        let mut highlights = match &controller.state {
+            AppState::Active(planner) => planner.highlights(),
+            AppState::Config { .. } => Vec::new(),
+        };
+        let report = &controller.heuristic_report;
+        if let Some(report) = report {
+            highlights.extend(report.overestimates.iter().map(|&id| (id, Highlight::Overestimate)));
+            highlights.extend(report.triangle_breaks.iter().map(|&id| (id, Highlight::TriangleBreak)));
+        }
+        {
+            for (id, highlight) in highlights {
+                let (ind_x, ind_y) = controller.world().coords_for(id).unwrap();
+                let cell_rect = [
+                    settings.position[0] + ind_x as f64 * cell_size,
+                    settings.position[1] + ind_y as f64 * cell_size,
+                    cell_size, cell_size
+                ];
+                let color = match highlight {
+                    Highlight::Updated => settings.updated_cell_color,
+                    Highlight::Inconsistent => settings.inconsistent_cell_color,
+                    Highlight::JumpPoint => settings.jump_point_color,
+                    Highlight::Pruned => settings.pruned_cell_color,
+                    Highlight::ForwardVisited => settings.forward_visited_color,
+                    Highlight::BackwardVisited => settings.backward_visited_color,
+                    Highlight::Meeting => settings.meeting_cell_color,
+                    Highlight::OnStack => settings.stack_cell_color,
+                    Highlight::Overestimate => settings.overestimate_cell_color,
+                    Highlight::TriangleBreak => settings.triangle_break_cell_color,
+                };
+                Rectangle::new(color).draw(cell_rect, &c.draw_state, c.transform, g);
+            }
+        }
+
         // Draw selected cell border as bold
-        if let AppState::Active(astar) = &controller.state {
-            if let Some(cell) = astar.current() {
-                let (ind_x, ind_y) = astar.world_view().coords_for(cell).unwrap();
+        if let AppState::Active(planner) = &controller.state {
+            if let Some(cell) = planner.current() {
+                let (ind_x, ind_y) = planner.world_view().coords_for(cell).unwrap();
                 let pos = [ind_x as f64 * cell_size, ind_y as f64 * cell_size];
                 let cell_rect = [
                     settings.position[0] + pos[0], settings.position[1] + pos[1],
@@ -247,34 +340,41 @@ impl WorldView {
 
         // Draw cell borders.
         let cell_edge = Line::new(settings.cell_edge_color, settings.cell_edge_radius);
+        let x2 = settings.position[0] + board_width;
+        let y2 = settings.position[1] + board_height;
         for i in 0..controller.world().width() {
-
-            let x = settings.position[0] + i as f64 / controller.world().width() as f64 * settings.size;
-            let y = settings.position[1] + i as f64 / controller.world().height() as f64 * settings.size;
-            let x2 = settings.position[0] + settings.size;
-            let y2 = settings.position[1] + settings.size;
-
+            let x = settings.position[0] + i as f64 * cell_size;
             let vline = [x, settings.position[1], x, y2];
             cell_edge.draw(vline, &c.draw_state, c.transform, g);
-
+        }
+        for j in 0..controller.world().height() {
+            let y = settings.position[1] + j as f64 * cell_size;
             let hline = [settings.position[0], y, x2, y];
             cell_edge.draw(hline, &c.draw_state, c.transform, g);
         }
 
         // Draw path
         let path_line = Line::new_round(settings.path_line_color, settings.path_line_radius);
-        if let AppState::Active(astar) = &controller.state {
-            if let Some(path) = astar.path() {
+        if let AppState::Active(planner) = &controller.state {
+            if let Some(path) = planner.path() {
                 let mut ids = path.iter();
-                let mut prev_coord = astar.world_view().coords_for(*ids.next().unwrap()).unwrap();
+                let mut prev_coord = planner.world_view().coords_for(*ids.next().unwrap()).unwrap();
                 for id in ids {
                     let (x1,y1) = prev_coord;
-                    let (x2,y2) = astar.world_view().coords_for(*id).unwrap();
+                    let (x2,y2) = planner.world_view().coords_for(*id).unwrap();
                     path_line.draw([x1 as f64 * cell_size + cell_size * 0.5 + settings.position[0],
                                     y1 as f64 * cell_size + cell_size * 0.5 + settings.position[1],
                                     x2 as f64 * cell_size + cell_size * 0.5 + settings.position[0],
                                     y2 as f64 * cell_size + cell_size * 0.5 + settings.position[1]],
                                     &c.draw_state, c.transform, g);
+                    // label any-angle segments that skip over cells
+                    if x1.max(x2) - x1.min(x2) > 1 || y1.max(y2) - y1.min(y2) > 1 {
+                        let from = planner.world_view().id_at(x1, y1).unwrap();
+                        let length = planner.world_view().distance(from, *id).unwrap();
+                        self.write_anywhere(((x1 + x2) as f64 * 0.5 * cell_size + cell_size * 0.5,
+                                             (y1 + y2) as f64 * 0.5 * cell_size + cell_size * 0.5),
+                                            &format!("{:0.2}", length), glyphs, c, g);
+                    }
                     prev_coord = (x2, y2);
                 }
             }
@@ -286,13 +386,19 @@ impl WorldView {
                 let cell = controller.world().cell_at(i, j).unwrap();
 
                 // Fill visited
-                if let Cell::Visited{g: goalcost, h: heurcost, k: _, parent} = cell {
-                    self.write_cell(cell_size, (i,j), (0.0, (settings.font_size * 0) as f64), 
+                if !cell_text { continue }
+                if let Cell::Visited{g: goalcost, h: heurcost, k: key, parent} = cell {
+                    self.write_cell(cell_size, (i,j), (0.0, 0.0), 
                                     &format!("g: {:0.1}", goalcost), glyphs, c, g);                     
-                    self.write_cell(cell_size, (i,j), (0.0, (settings.font_size * 1) as f64), 
+                    self.write_cell(cell_size, (i,j), (0.0, settings.font_size as f64), 
                                     &format!("h: {:0.1}", heurcost), glyphs, c, g);                     
                     self.write_cell(cell_size, (i,j), (0.0, (settings.font_size * 2) as f64), 
                                     &format!("p: {:?}", controller.world().coords_for(*parent).unwrap()), glyphs, c, g);                     
+                    // only the incremental planners use a key value
+                    if *key != 0.0 {
+                        self.write_cell(cell_size, (i,j), (0.0, (settings.font_size * 3) as f64), 
+                                        &format!("k: {:0.1}", key), glyphs, c, g);                     
+                    }
                 };
 
             }
@@ -303,11 +409,43 @@ impl WorldView {
         Rectangle::new_border(settings.board_edge_color, settings.board_edge_radius)
             .draw(board_rect, &c.draw_state, c.transform, g);
 
+        // Draw a banner over the middle of the board if the search came up empty
+        if let Some(StepResult::NoPath { .. }) = controller.outcome {
+            let height = settings.font_size as f64 * 3.0;
+            let banner_rect = [
+                settings.position[0], settings.position[1] + 0.5 * (board_height - height),
+                board_width, height,
+            ];
+            Rectangle::new(settings.banner_color)
+                .draw(banner_rect, &c.draw_state, c.transform, g);
+            Rectangle::new_border(settings.board_edge_color, settings.board_edge_radius)
+                .draw(banner_rect, &c.draw_state, c.transform, g);
+            self.write_anywhere((settings.font_size as f64,
+                                 0.5 * (board_height + settings.font_size as f64)),
+                                "Unreachable: no path from start to goal",
+                                glyphs, c, g);
+        }
+
         // Draw controlls (another column past the board of 1x2 cells)
         let mut labels: Vec<String> = Vec::new();
         let mut stats: Vec<String> = Vec::new();
+        if let Some(report) = report {
+            stats.push(if report.admissible() {
+                "Heuristic is admissible".to_string()
+            } else {
+                format!("Heuristic overestimates at {} cells", report.overestimates.len())
+            });
+            stats.push(if report.consistent() {
+                "Heuristic is consistent".to_string()
+            } else {
+                format!("Heuristic is inconsistent at {} cells", report.triangle_breaks.len())
+            });
+            stats.push(format!("Optimal path cost: {:0.2}", report.optimal_cost));
+            stats.push(String::new());
+        }
         match &controller.state {
-            AppState::Config{cfg, world} => {
+            AppState::Config{cfg, world, algorithm} => {
+                labels.push(format!("Algorithm: {}", algorithm));
                 labels.push(
                     match cfg.neighbors { 
                         Neighbors::CardinalAndDiagonal => "Diagonal: Yes".to_string(),
@@ -315,70 +453,183 @@ impl WorldView {
                     }
                 );
                 labels.push(
-                    match cfg.heuristic { 
-                        Some(Heuristic::Manhattan) => "Heuristic: Manhattan".to_string(),
-                        Some(Heuristic::Euclidean) => "Heuristic: Euclidean".to_string(),
+                    match &cfg.heuristic { 
+                        Some(h) => format!("Heuristic: {}", h),
                         None => "Heuristic: None".to_string(),
                     }
                 );
-                let mut message = "Left click to toggle obstacles, ".to_string();
-                if let Ok(_) = cfg.valid_for(&world) {
-                    labels.push("Start".to_string());
-                    message = message + "Right click to reset Start and Goal.";
+                labels.push(if algorithm.uses_weight() {
+                    format!("A* Weight: {:0.1} (<= {:0.1}x)", cfg.weight, cfg.weight)
                 } else {
-                    message = message + "Right click to set Start and Goal.";
+                    "A* Weight: n/a".to_string()
+                });
+                labels.push(format!("Ties: {}", cfg.tie_break));
+                labels.push(format!("Direction: {}", cfg.direction));
+                labels.push(
+                    match controller.brush {
+                        Brush::Obstacle => "Paint: Obstacle".to_string(),
+                        Brush::Terrain(t) => format!("Paint: {} (x{})", t, t.cost()),
+                    }
+                );
+                labels.push(format!("Check heuristic: {}",
+                                    if controller.check_heuristic { "On" } else { "Off" }));
+                labels.push(format!("- Width: {} +", world.width()));
+                labels.push(format!("- Height: {} +", world.height()));
+                let mut message = match controller.brush {
+                    Brush::Obstacle => "Left click to toggle obstacles, ".to_string(),
+                    Brush::Terrain(t) => format!("Left click to paint {}, ", t),
+                };
+                match algorithm.valid_for(cfg, world) {
+                    Ok(_) => {
+                        message += "Right click to reset Start and Goal.";
+                        labels.push("Start".to_string());
+                    }
+                    Err(PlanningError::MissingStart) | Err(PlanningError::MissingGoal) =>
+                        message += "Right click to set Start and Goal.",
+                    Err(reason) => message += &format!("Right click to reset Start and Goal. {}.", reason),
                 }
                 self.write_anywhere((self.settings.position[0], 
-                                     self.settings.position[1] + self.settings.size), 
+                                     self.settings.position[1] + board_height), 
                                      &message,
                                      glyphs, c, g);       
             },
-            AppState::Active(astar) => { 
-                let cell_string : String  = if let Some(id) = astar.current() {
-                    let (x,y) = astar.world_view().coords_for(id).unwrap();
+            AppState::Active(planner) => { 
+                let cell_string : String  = if let Some(id) = planner.current() {
+                    let (x,y) = planner.world_view().coords_for(id).unwrap();
                     let cost = if let Cell::Visited{g: goalcost, h: heurcost, k: _, parent:_} = 
-                                      astar.world_view().cell(id).unwrap() {
+                                      planner.world_view().cell(id).unwrap() {
                             goalcost + heurcost
                         } else { 0.0 };
                     format!("Current Cell: ({},{}) f: {:0.1}",x,y,cost)
                 } else { String::new() };
 
                 labels.push( "Next".to_string() );
+                labels.push( "Prev".to_string() );
                 labels.push( "Reset".to_string() );
+                // step numbers, the oldest history may have been dropped
+                // a run in progress is ahead of the history
+                let last = controller.history.last().map_or(0, |snapshot| snapshot.step);
+                labels.push( format!("Step {} of {}", controller.step, last.max(controller.step)) );
+                labels.push( if controller.playing { "Pause" } else { "Play" }.to_string() );
+                labels.push( format!("Speed: {} steps/s", controller.steps_per_second) );
+                labels.push( if controller.running { "Stop" } else { "Run to end" }.to_string() );
+                if let Some(idx) = planner.selected_solution() {
+                    labels.push( format!("Solution {} of {}", idx + 1, planner.solution_count()) );
+                }
                 stats.push( cell_string );
+                match controller.outcome {
+                    Some(StepResult::Expanded { cell, opened, .. }) => {
+                        let (x,y) = planner.world_view().coords_for(cell).unwrap();
+                        stats.push( format!("Last step: expanded ({},{}), opened {}", x, y, opened) );
+                    }
+                    Some(StepResult::Found { cost, .. }) =>
+                        stats.push( format!("Last step: found path costing {:0.2}", cost) ),
+                    Some(StepResult::NoPath { .. }) =>
+                        stats.push( "Last step: no path".to_string() ),
+                    _ => {}
+                }
+                if let Some(path) = planner.path() {
+                    let world = planner.world_view();
+                    let length: f32 = path.windows(2)
+                        .map(|w| world.distance(w[0], w[1]).unwrap())
+                        .sum();
+                    stats.push( format!("Path length: {:0.2}", length) );
+                    if !world.uniform_terrain() {
+                        let cost: f32 = path.windows(2)
+                            .map(|w| world.segment_cost(w[0], w[1]).unwrap())
+                            .sum();
+                        stats.push( format!("Path terrain cost: {:0.2}", cost) );
+                    }
+                }
+                stats.extend( planner.stats() );
                 stats.push(  String::new() );
-                stats.push( "Frontier:".to_string() );
-                let fview = astar.frontier_view();
-                for idx in 1..(fview.len()+1) {
-                    let (id, cost) = fview.get(fview.len() - idx).unwrap();
-                    let (x,y) = astar.world_view().coords_for(*id).unwrap();
-                    stats.push(format!("  {}. ({},{}) f: {:0.1}",idx,x,y,cost))
+                stats.push( planner.frontier_title().to_string() );
+                for (idx, (id, cost)) in planner.frontier_view().iter().enumerate() {
+                    let (x,y) = planner.world_view().coords_for(*id).unwrap();
+                    stats.push(format!("  {}. ({},{}) f: {:0.1}",idx+1,x,y,cost))
                 }
-                let mut message = "Click next to advance planning. ".to_string();
-                message += match astar.config_view().heuristic { 
-                        None => "Using no heuristic",
-                        Some(Heuristic::Euclidean) => "Using Euclidean distance as hueristic",
-                        Some(Heuristic::Manhattan) => "Using Manhattan distance as hueristic",
+                let mut message = format!("Click next to advance {} planning. ", planner.algorithm());
+                if planner.supports_replanning() {
+                    message += "Left click to toggle obstacles and replan. ";
+                }
+                message += &match &planner.config_view().heuristic { 
+                        None => "Using no heuristic".to_string(),
+                        Some(h) => format!("Using {} distance as hueristic", h),
                 };
-                message += match astar.config_view().neighbors { 
+                message += match planner.config_view().neighbors { 
                         Neighbors::CardinalAndDiagonal => " and allowing diagonal moves.",
                         Neighbors::Cardinal => " and not allowing diagonal moves.",
                 };
                 self.write_anywhere((self.settings.position[0], 
-                                     self.settings.position[1] + self.settings.size), 
+                                     self.settings.position[1] + board_height), 
                                      &message,
                                      glyphs, c, g);       
             },
         }
         let mut index = 0;
         for label in labels {
-            self.draw_label((settings.size + 10.0, index as f64 * cell_size), (cell_size * 3.0, cell_size), &label, glyphs, c, g);       
+            self.draw_label((settings.size + 10.0, index as f64 * layout.row_height),
+                            (layout.sidebar_width, layout.row_height), &label, glyphs, c, g);       
             index += 1;
         }
-        let offset = index as f64 * cell_size;
-        for stat in stats {
-            self.write_anywhere((settings.size + 10.0, (index * self.settings.font_size) as f64 + offset), &stat, glyphs, c, g);       
-            index += 1;
+        // Knob on the timeline showing where in the history we are
+        if let AppState::Active(_) = &controller.state {
+            let last = controller.history.len().saturating_sub(1).max(1);
+            let width = layout.sidebar_width - 2.0 * settings.board_edge_radius;
+            let along = controller.history_pos as f64 / last as f64;
+            let knob = settings.font_size as f64 * 0.5;
+            let x = settings.position[0] + settings.size + 10.0 + settings.board_edge_radius + along * width;
+            let y = settings.position[1] + 4.0 * layout.row_height - 2.0 * settings.board_edge_radius - knob;
+            Rectangle::new(settings.timeline_color)
+                .draw([x - knob * 0.5, y, knob, knob], &c.draw_state, c.transform, g);
+        }
+        // The step log takes the bottom of the sidebar, stats that run long
+        // are cut off above it
+        let line_height = settings.font_size as f64;
+        let log_height = (settings.log_lines + 1) as f64 * line_height + line_height * 0.5;
+        let log_shown = match &controller.state {
+            AppState::Active(_) => !controller.visible_log().is_empty(),
+            AppState::Config { .. } => false,
+        };
+        let stats_end = if log_shown { settings.size - log_height } else { f64::INFINITY };
+        let offset = index as f64 * layout.row_height;
+        let stat_y = |line: usize| (index + line) as f64 * line_height + offset;
+        let fits = (0..stats.len())
+            .take_while(|&line| stat_y(line) + line_height * 0.25 <= stats_end)
+            .count();
+        for (line, stat) in stats.iter().take(fits).enumerate() {
+            let text = if fits < stats.len() && line + 1 == fits {
+                format!("... {} more lines", stats.len() - line)
+            } else {
+                stat.clone()
+            };
+            self.write_anywhere((settings.size + 10.0, stat_y(line)), &text, glyphs, c, g);
+        }
+
+        if let AppState::Active(planner) = &controller.state {
+            let log = controller.visible_log();
+            if log_shown {
+                let top = stats_end;
+                let log_rect = [
+                    settings.position[0] + settings.size + 10.0, settings.position[1] + top,
+                    layout.sidebar_width, log_height,
+                ];
+                Rectangle::new(settings.log_background_color)
+                    .draw(log_rect, &c.draw_state, c.transform, g);
+                Rectangle::new_border(settings.cell_edge_color, settings.cell_edge_radius)
+                    .draw(log_rect, &c.draw_state, c.transform, g);
+
+                let end = log.len() - controller.log_scroll.min(log.len());
+                let begin = end.saturating_sub(settings.log_lines);
+                self.write_anywhere((settings.size + 15.0, top + line_height),
+                                    &format!("Log {}-{} of {} (scroll):", begin + 1, end, log.len()),
+                                    glyphs, c, g);
+                for (line, &(step, event)) in log[begin..end].iter().enumerate() {
+                    self.write_anywhere((settings.size + 15.0, top + (line + 2) as f64 * line_height),
+                                        &format!("{:>3} {}", step, event.describe(planner.world_view())),
+                                        glyphs, c, g);
+                }
+            }
         }
     }
 }